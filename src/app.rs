@@ -2,11 +2,13 @@ use leptos::*;
 use leptos_meta::*;
 use leptos_router::*;
 
+pub mod auth;
 mod clerk;
 mod engine;
 mod home;
+mod local;
 
-use clerk::*;
+use auth::*;
 use engine::*;
 use home::*;
 
@@ -14,8 +16,11 @@ use crate::list::List;
 
 #[derive(Clone)]
 struct AppState {
-    clerk: RwSignal<Option<Clerk>>,
+    session: RwSignal<Option<AuthSession>>,
+    provider: RwSignal<Option<AuthProviderKind>>,
     list: RwSignal<Option<List>>,
+    /// The stored list when the last save lost to a concurrent change.
+    conflict: RwSignal<Option<List>>,
 }
 
 #[component]
@@ -26,8 +31,10 @@ pub fn App(cx: Scope) -> impl IntoView {
     provide_context(
         cx,
         AppState {
-            clerk: create_rw_signal(cx, None),
+            session: create_rw_signal(cx, None),
+            provider: create_rw_signal(cx, None),
             list: create_rw_signal(cx, None),
+            conflict: create_rw_signal(cx, None),
         },
     );
 
@@ -46,8 +53,8 @@ pub fn App(cx: Scope) -> impl IntoView {
             <ion-content class="no-scrollbar">
                 <Router>
 
-                    // Load Clerk
-                    <Clerk/>
+                    // Load the sign in of the configured provider
+                    <Auth/>
 
                     <main>
                         <Routes>
@@ -76,7 +83,7 @@ fn Header(cx: Scope) -> impl IntoView {
                     "Donation Engine"
                 </ion-title>
                 <ion-buttons slot="end">
-                    <ClerkButtons/>
+                    <AuthButtons/>
                 </ion-buttons>
             </ion-toolbar>
         </ion-header>
@@ -0,0 +1,204 @@
+use chrono::{DateTime, Utc};
+use leptos::*;
+use leptos_router::use_navigate;
+use serde::{Deserialize, Serialize};
+
+use super::{clerk, clerk::Clerk, local, local::LocalAuth, AppState};
+
+/// Who signs users in. The server picks one at startup and the client renders its sign in.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum AuthProviderKind {
+    Clerk,
+    Local,
+}
+
+/// The signed in user, whichever provider signed them in.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct AuthSession {
+    pub user_id: String,
+    pub session_id: String,
+}
+
+#[server(GetAuthProvider, "/api")]
+pub async fn get_auth_provider(cx: Scope) -> Result<AuthProviderKind, ServerFnError> {
+    use crate::clerk_middleware::provider::AuthProvider;
+    use actix_web::web::Data;
+
+    leptos_actix::extract(cx, |provider: Data<dyn AuthProvider>| async move {
+        provider.kind()
+    })
+    .await
+}
+
+/// A verified session, as the server sees it.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct SessionInfo {
+    pub user_id: String,
+    pub session_id: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<SessionInfo> for AuthSession {
+    fn from(info: SessionInfo) -> Self {
+        Self {
+            user_id: info.user_id,
+            session_id: info.session_id,
+        }
+    }
+}
+
+/// The session of the request's token, checked the same way `ClerkMiddleware` does. `None` when
+/// there is no token or it is not valid, an error only when the provider cannot tell.
+#[server(IsLoggedIn, "/api")]
+pub async fn is_logged_in(cx: Scope) -> Result<Option<SessionInfo>, ServerFnError> {
+    use crate::clerk_middleware::{clerk_authorize, provider::AuthProvider, AuthError};
+    use actix_web::{web::Data, HttpRequest};
+    use chrono::TimeZone;
+
+    let claims = leptos_actix::extract(
+        cx,
+        |req: HttpRequest, provider: Data<dyn AuthProvider>| async move {
+            clerk_authorize(&req, provider.as_ref()).await
+        },
+    )
+    .await?;
+
+    match claims {
+        Ok(claims) => Ok(Utc
+            .timestamp_opt(claims.exp.into(), 0)
+            .single()
+            .map(|expires_at| SessionInfo {
+                user_id: claims.sub,
+                session_id: claims.sid,
+                expires_at,
+            })),
+        Err(AuthError::JwksUnavailable) => Err(ServerFnError::ServerError(
+            "Issue verifying session.".to_owned(),
+        )),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Loads the sign in of the configured provider and sends users to the engine once they are
+/// signed in.
+#[component]
+pub fn Auth(cx: Scope) -> impl IntoView {
+    let app_state = expect_context::<AppState>(cx);
+
+    // Only asked from the browser, the sign in scripts do not run on the server anyway
+    let provider = create_local_resource(cx, || (), move |_| get_auth_provider(cx));
+
+    create_effect(cx, move |_| {
+        if let Some(Ok(kind)) = provider.read(cx) {
+            app_state.provider.set(Some(kind));
+        }
+    });
+
+    create_effect(cx, move |_| {
+        let is_logged_in = app_state.session.with(Option::is_some);
+
+        let navigate = use_navigate(cx);
+        if is_logged_in {
+            let _ = navigate("/engine", Default::default());
+        } else {
+            let _ = navigate("/", Default::default());
+        }
+    });
+
+    move || match app_state.provider.get() {
+        Some(AuthProviderKind::Clerk) => view! { cx, <Clerk/> }.into_view(cx),
+        Some(AuthProviderKind::Local) => view! { cx, <LocalAuth/> }.into_view(cx),
+        None => ().into_view(cx),
+    }
+}
+
+#[component]
+pub fn AuthButtons(cx: Scope) -> impl IntoView {
+    let app_state = expect_context::<AppState>(cx);
+
+    view! { cx,
+        <Show
+            when= move || app_state.session.with(Option::is_some)
+            fallback=|cx| view! { cx, <SignIn/> }
+        >
+            <UserMenu/>
+        </Show>
+    }
+}
+
+#[component]
+fn SignIn(cx: Scope) -> impl IntoView {
+    let app_state = expect_context::<AppState>(cx);
+
+    let sign_in_handler = move |_| match app_state.provider.get_untracked() {
+        Some(AuthProviderKind::Clerk) => clerk::open_sign_in(),
+        Some(AuthProviderKind::Local) => local::sign_in(cx),
+        None => {}
+    };
+
+    view! { cx,
+        <ion-button
+            id="sign-in-button"
+            color="secondary"
+            fill="solid"
+            on:click=sign_in_handler
+        >
+            <div class="hidden md:block">"Login/Signup"</div>
+            <ion-icon class="hidden md:block" slot="end" name="log-in-outline"></ion-icon>
+            <ion-icon class="md:hidden" name="log-in-outline"></ion-icon>
+        </ion-button>
+    }
+}
+
+#[component]
+fn UserMenu(cx: Scope) -> impl IntoView {
+    let app_state = expect_context::<AppState>(cx);
+
+    // Local users have no profile to edit
+    let has_profile = move || app_state.provider.get() == Some(AuthProviderKind::Clerk);
+
+    let open_user_profile_handler = |_| clerk::open_user_profile();
+
+    let sign_out_handler = move |_| match app_state.provider.get_untracked() {
+        Some(AuthProviderKind::Clerk) => clerk::sign_out(),
+        Some(AuthProviderKind::Local) => local::sign_out(cx),
+        None => {}
+    };
+
+    view! { cx,
+        <ion-button
+            id="user-button"
+            color="secondary"
+            fill="solid"
+        >
+            "User"
+            <ion-icon slot="end" name="chevron-down-circle"></ion-icon>
+        </ion-button>
+        <ion-popover trigger="user-button" prop:dismissOnSelect="true">
+            <ion-content>
+                <ion-list>
+                    <Show when=has_profile fallback=|_| ()>
+                        <ion-item
+                            id="user-button"
+                            button="true"
+                            detail="false"
+                            on:click=open_user_profile_handler
+                        >
+                            "Settings"
+                            <ion-icon slot="end" name="person-circle-outline"></ion-icon>
+                        </ion-item>
+                    </Show>
+                    <ion-item
+                        id="sign-out-button"
+                        button="true"
+                        detail="false"
+                        on:click=sign_out_handler
+                    >
+                        "Logout"
+                        <ion-icon slot="end" name="log-out-outline"></ion-icon>
+                    </ion-item>
+                </ion-list>
+            </ion-content>
+        </ion-popover>
+    }
+}
@@ -1,39 +1,9 @@
 use leptos::*;
-use leptos_router::use_navigate;
 use serde::{Deserialize, Serialize};
 use serde_wasm_bindgen::from_value;
 use wasm_bindgen::prelude::{wasm_bindgen, Closure};
 
-use super::AppState;
-
-#[server(IsLoggedIn, "/isloggedin")]
-pub async fn is_logged_in(cx: Scope, session_id: String) -> Result<bool, ServerFnError> {
-    use actix_web::HttpRequest;
-    use reqwest::StatusCode;
-    use std::collections::HashMap;
-
-    leptos_actix::extract(cx, |req: HttpRequest| async move {
-        let session_cookie = req.cookie("__session").unwrap();
-        println!("{}", session_cookie.to_string());
-
-        let mut map = HashMap::new();
-        map.insert("token", session_cookie.value().to_owned());
-
-        let status = reqwest::Client::new()
-            .post(format!(
-                r#"https://api.clerk.com/v1/sessions/{session_id}/verify"#
-            ))
-            .json(&map)
-            .send()
-            .await
-            .unwrap()
-            .status();
-
-        println!("{}", status.to_string());
-        status == StatusCode::OK
-    })
-    .await
-}
+use super::{auth::AuthSession, AppState};
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -43,6 +13,18 @@ pub struct Clerk {
     pub user: Option<UserResource>,
 }
 
+impl Clerk {
+    /// The session of the signed in user, `None` while signed out.
+    pub fn into_session(self) -> Option<AuthSession> {
+        let (user, session) = self.user.zip(self.session)?;
+
+        Some(AuthSession {
+            user_id: user.id,
+            session_id: session.id,
+        })
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ClientResource {
@@ -89,6 +71,18 @@ fn get_clerk_from_js() -> ClerkJsObj {
     clerk.unchecked_into::<ClerkJsObj>()
 }
 
+pub fn open_sign_in() {
+    get_clerk_from_js().open_sign_in();
+}
+
+pub fn open_user_profile() {
+    get_clerk_from_js().open_user_profile();
+}
+
+pub fn sign_out() {
+    get_clerk_from_js().sign_out();
+}
+
 #[component]
 pub fn Clerk(cx: Scope) -> impl IntoView {
     let app_state = expect_context::<AppState>(cx);
@@ -102,27 +96,14 @@ pub fn Clerk(cx: Scope) -> impl IntoView {
         let closure = Closure::wrap(Box::new(move |resources: JsValue| {
             let clerk: Clerk =
                 from_value(resources).expect("Unable to load Clerk object from window.");
-            app_state.clerk.set(Some(clerk));
-            log!("{:?}", app_state.clerk.get_untracked());
+            app_state.session.set(clerk.into_session());
+            log!("{:?}", app_state.session.get_untracked());
         }) as Box<dyn Fn(JsValue)>);
 
         clerk.add_listener(&closure);
         closure.forget();
     };
 
-    create_effect(cx, move |_| {
-        let is_logged_in = app_state
-            .clerk
-            .with(|clerk| clerk.as_ref().is_some_and(|clerk| clerk.user.is_some()));
-
-        let navigate = use_navigate(cx);
-        if is_logged_in {
-            let _ = navigate("/engine", Default::default());
-        } else {
-            let _ = navigate("/", Default::default());
-        }
-    });
-
     view! { cx,
         // Load Clerk
         <script
@@ -136,86 +117,3 @@ pub fn Clerk(cx: Scope) -> impl IntoView {
         </script>
     }
 }
-
-#[component]
-pub fn ClerkButtons(cx: Scope) -> impl IntoView {
-    let app_state = expect_context::<AppState>(cx);
-
-    view! { cx,
-        <Show
-            when= move || app_state.clerk.get().is_some_and(|clerk| clerk.session.is_some())
-            fallback=|cx| view! { cx, <ClerkSignIn/> }
-        >
-            <ClerkUser/>
-        </Show>
-    }
-}
-
-#[component]
-fn ClerkSignIn(cx: Scope) -> impl IntoView {
-    let sign_in_handler = |_| {
-        let clerk = get_clerk_from_js();
-        clerk.open_sign_in();
-    };
-
-    view! { cx,
-        <ion-button
-            id="sign-in-button"
-            color="secondary"
-            fill="solid"
-            on:click=sign_in_handler
-        >
-            <div class="hidden md:block">"Login/Signup"</div>
-            <ion-icon class="hidden md:block" slot="end" name="log-in-outline"></ion-icon>
-            <ion-icon class="md:hidden" name="log-in-outline"></ion-icon>
-        </ion-button>
-    }
-}
-
-#[component]
-fn ClerkUser(cx: Scope) -> impl IntoView {
-    let open_user_profile_handler = |_| {
-        let clerk = get_clerk_from_js();
-        clerk.open_user_profile();
-    };
-
-    let sign_out_handler = |_| {
-        let clerk = get_clerk_from_js();
-        clerk.sign_out();
-    };
-
-    view! { cx,
-        <ion-button
-            id="user-button"
-            color="secondary"
-            fill="solid"
-        >
-            "User"
-            <ion-icon slot="end" name="chevron-down-circle"></ion-icon>
-        </ion-button>
-        <ion-popover trigger="user-button" prop:dismissOnSelect="true">
-            <ion-content>
-                <ion-list>
-                    <ion-item
-                        id="user-button"
-                        button="true"
-                        detail="false"
-                        on:click=open_user_profile_handler
-                    >
-                        "Settings"
-                        <ion-icon slot="end" name="person-circle-outline"></ion-icon>
-                    </ion-item>
-                    <ion-item
-                        id="sign-out-button"
-                        button="true"
-                        detail="false"
-                        on:click=sign_out_handler
-                    >
-                        "Logout"
-                        <ion-icon slot="end" name="log-out-outline"></ion-icon>
-                    </ion-item>
-                </ion-list>
-            </ion-content>
-        </ion-popover>
-    }
-}
@@ -1,59 +1,943 @@
-use crate::{app::AppState, list::*};
+use crate::{
+    app::AppState,
+    list::{conflict::*, history::*, scenario::*, transfer::*, *},
+    live::ListUpdate,
+    tax::{FilingStatus, TaxEstimate, TaxProfile},
+};
+use chrono::NaiveDate;
 use leptos::*;
+use rust_decimal::Decimal;
+use wasm_bindgen::{prelude::Closure, JsCast};
+use web_sys::{EventSource, MessageEvent};
 
 #[server(GetList, "/api")]
-pub async fn get_list(cx: Scope, user_id: String) -> Result<List, ServerFnError> {
+pub async fn get_list(cx: Scope) -> Result<List, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
     use actix_web::web::Data;
     use sea_orm::DatabaseConnection;
 
-    leptos_actix::extract(cx, |db: Data<DatabaseConnection>| async move {
-        let list: Option<List> = List::from_user_id(db.get_ref(), user_id.clone()).await;
+    leptos_actix::extract(
+        cx,
+        |db: Data<DatabaseConnection>, user: CurrentUser| async move {
+            let list: Option<List> = List::from_user_id(db.get_ref(), user.id.clone()).await;
 
-        if let Some(list) = list {
-            return Ok(list);
-        }
+            if let Some(list) = list {
+                return Ok(list);
+            }
 
-        let list: Option<List> = List::init_list(db.get_ref(), user_id).await;
+            List::init_list(db.get_ref(), user.id).await.ok_or_else(|| {
+                ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+            })
+        },
+    )
+    .await?
+}
 
-        if let Some(list) = list {
-            return Ok(list);
-        } else {
-            return Err(ServerFnError::ServerError(
-                "Issue retrieving list from database.".to_owned(),
+#[server(ExportList, "/api")]
+pub async fn export_list(cx: Scope, format: TransferFormat) -> Result<String, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, user: CurrentUser| async move {
+            let list = List::from_user_id(db.get_ref(), user.id)
+                .await
+                .ok_or_else(|| {
+                    ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+                })?;
+
+            let export = ListExport::from(&list);
+            match format {
+                TransferFormat::Csv => export.to_csv().map_err(|error| error.to_string()),
+                TransferFormat::Json => export.to_json().map_err(|error| error.to_string()),
+            }
+            .map_err(ServerFnError::ServerError)
+        },
+    )
+    .await?
+}
+
+#[server(PreviewImport, "/api")]
+pub async fn preview_import(
+    cx: Scope,
+    format: TransferFormat,
+    mode: ImportMode,
+    data: String,
+) -> Result<ImportPreview, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, user: CurrentUser| async move {
+            let list = List::from_user_id(db.get_ref(), user.id)
+                .await
+                .ok_or_else(|| {
+                    ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+                })?;
+            let import = ListExport::parse(format, &data).map_err(import_issues_error)?;
+
+            let result = list.apply_import(&import, mode);
+            Ok(list.preview_changes(&result, mode))
+        },
+    )
+    .await?
+}
+
+#[server(ImportList, "/api")]
+pub async fn import_list(
+    cx: Scope,
+    format: TransferFormat,
+    mode: ImportMode,
+    data: String,
+    version: i32,
+) -> Result<SaveOutcome, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use crate::live::ListHub;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, hub: Data<ListHub>, user: CurrentUser| async move {
+            let list = List::from_user_id(db.get_ref(), user.id.clone())
+                .await
+                .ok_or_else(|| {
+                    ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+                })?;
+            let import = ListExport::parse(format, &data).map_err(import_issues_error)?;
+
+            let result = List {
+                version,
+                ..list.apply_import(&import, mode)
+            }
+            .save(db.get_ref(), &user.id)
+            .await;
+            save_outcome(result, &hub, &user.id, "Issue saving imported list.")
+        },
+    )
+    .await?
+}
+
+#[server(SaveBudget, "/api")]
+pub async fn save_budget(
+    cx: Scope,
+    budget: Budget,
+    version: i32,
+) -> Result<SaveOutcome, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use crate::live::ListHub;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    validate_budget(&budget)?;
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, hub: Data<ListHub>, user: CurrentUser| async move {
+            let list = List::from_user_id(db.get_ref(), user.id.clone())
+                .await
+                .ok_or_else(|| {
+                    ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+                })?;
+
+            let result = List {
+                version,
+                budget: Budget {
+                    id: list.budget.id,
+                    list_id: list.id,
+                    ..budget
+                },
+                ..list
+            }
+            .save(db.get_ref(), &user.id)
+            .await;
+            save_outcome(result, &hub, &user.id, "Issue saving budget.")
+        },
+    )
+    .await?
+}
+
+#[server(SaveList, "/api")]
+pub async fn save_list(cx: Scope, list: List) -> Result<SaveOutcome, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use crate::live::ListHub;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    validate_budget(&list.budget)?;
+    list.validate().map_err(import_issues_error)?;
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, hub: Data<ListHub>, user: CurrentUser| async move {
+            let stored = List::from_user_id(db.get_ref(), user.id.clone())
+                .await
+                .ok_or_else(|| {
+                    ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+                })?;
+
+            let result = List {
+                id: stored.id,
+                user_id: stored.user_id,
+                scenario_of: stored.scenario_of,
+                ..list
+            }
+            .save(db.get_ref(), &user.id)
+            .await;
+            save_outcome(result, &hub, &user.id, "Issue saving list.")
+        },
+    )
+    .await?
+}
+
+#[server(GetScenarios, "/api")]
+pub async fn get_scenarios(cx: Scope) -> Result<Vec<List>, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, user: CurrentUser| async move {
+            let list = List::from_user_id(db.get_ref(), user.id)
+                .await
+                .ok_or_else(|| {
+                    ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+                })?;
+
+            List::scenarios_of(db.get_ref(), list.id)
+                .await
+                .map_err(|_| ServerFnError::ServerError("Issue retrieving scenarios.".to_owned()))
+        },
+    )
+    .await?
+}
+
+#[server(CreateScenario, "/api")]
+pub async fn create_scenario(cx: Scope, name: String) -> Result<List, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    let name = name.trim().to_owned();
+    if name.is_empty() {
+        return Err(ServerFnError::Args(
+            "Scenario name must not be empty.".to_owned(),
+        ));
+    }
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, user: CurrentUser| async move {
+            let list = List::from_user_id(db.get_ref(), user.id.clone())
+                .await
+                .ok_or_else(|| {
+                    ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+                })?;
+
+            list.fork_scenario(db.get_ref(), name, &user.id)
+                .await
+                .map_err(|_| ServerFnError::ServerError("Issue creating scenario.".to_owned()))
+        },
+    )
+    .await?
+}
+
+#[server(SaveScenario, "/api")]
+pub async fn save_scenario(cx: Scope, scenario: List) -> Result<SaveOutcome, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use crate::live::ListHub;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, hub: Data<ListHub>, user: CurrentUser| async move {
+            let (_, stored) = load_scenario(db.get_ref(), user.id.clone(), scenario.id).await?;
+
+            let result = List {
+                id: stored.id,
+                user_id: stored.user_id,
+                scenario_of: stored.scenario_of,
+                ..scenario
+            }
+            .save(db.get_ref(), &user.id)
+            .await;
+            save_outcome(result, &hub, &user.id, "Issue saving scenario.")
+        },
+    )
+    .await?
+}
+
+#[server(CompareScenario, "/api")]
+pub async fn compare_scenario(cx: Scope, scenario_id: i32) -> Result<ScenarioDiff, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, user: CurrentUser| async move {
+            let (list, scenario) = load_scenario(db.get_ref(), user.id, scenario_id).await?;
+
+            Ok(list.compare_scenario(&scenario))
+        },
+    )
+    .await?
+}
+
+#[server(PromoteScenario, "/api")]
+pub async fn promote_scenario(
+    cx: Scope,
+    scenario_id: i32,
+    version: i32,
+) -> Result<SaveOutcome, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use crate::live::ListHub;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, hub: Data<ListHub>, user: CurrentUser| async move {
+            let (list, scenario) =
+                load_scenario(db.get_ref(), user.id.clone(), scenario_id).await?;
+
+            let result = list
+                .promote_scenario(db.get_ref(), &scenario, version, &user.id)
+                .await;
+            save_outcome(result, &hub, &user.id, "Issue promoting scenario.")
+        },
+    )
+    .await?
+}
+
+#[server(DeleteScenario, "/api")]
+pub async fn delete_scenario(cx: Scope, scenario_id: i32) -> Result<(), ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use crate::entity::list::Entity as ListEntity;
+    use actix_web::web::Data;
+    use sea_orm::*;
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, user: CurrentUser| async move {
+            let (_, scenario) = load_scenario(db.get_ref(), user.id, scenario_id).await?;
+
+            ListEntity::delete_by_id(scenario.id)
+                .exec(db.get_ref())
+                .await
+                .map(|_| ())
+                .map_err(|_| ServerFnError::ServerError("Issue deleting scenario.".to_owned()))
+        },
+    )
+    .await?
+}
+
+#[cfg(feature = "ssr")]
+fn validate_budget(budget: &Budget) -> Result<(), ServerFnError> {
+    if budget.net_value.is_some_and(|value| value < Decimal::ZERO) {
+        return Err(ServerFnError::Args(
+            "Net income must not be negative.".to_owned(),
+        ));
+    }
+    for bracket in &budget.brackets {
+        if bracket.threshold < Decimal::ZERO {
+            return Err(ServerFnError::Args(
+                "Bracket thresholds must not be negative.".to_owned(),
+            ));
+        }
+        if bracket.percent < Decimal::ZERO || bracket.percent > Decimal::ONE {
+            return Err(ServerFnError::Args(
+                "Bracket percents must be between 0 and 1.".to_owned(),
             ));
         }
-    })
+    }
+
+    Ok(())
+}
+
+/// Turns the result of `List::save` into the outcome returned by the save server functions.
+/// Saved lists are published to the list's other subscribers.
+#[cfg(feature = "ssr")]
+fn save_outcome(
+    result: Result<List, SaveError>,
+    hub: &crate::live::ListHub,
+    actor: &str,
+    message: &str,
+) -> Result<SaveOutcome, ServerFnError> {
+    match result {
+        Ok(list) => {
+            hub.publish(&list, actor);
+            Ok(SaveOutcome::Saved(list))
+        }
+        Err(SaveError::Conflict(stored)) => Ok(SaveOutcome::Conflict(*stored)),
+        Err(SaveError::Database(_)) => Err(ServerFnError::ServerError(message.to_owned())),
+    }
+}
+
+/// Loads the user's live list and one of its scenarios.
+#[cfg(feature = "ssr")]
+async fn load_scenario(
+    db: &sea_orm::DatabaseConnection,
+    user_id: String,
+    scenario_id: i32,
+) -> Result<(List, List), ServerFnError> {
+    let list = List::from_user_id(db, user_id).await.ok_or_else(|| {
+        ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+    })?;
+
+    match List::from_id(db, scenario_id).await {
+        Some(scenario) if scenario.scenario_of == Some(list.id) => Ok((list, scenario)),
+        _ => Err(ServerFnError::ServerError(
+            "Issue retrieving scenario from database.".to_owned(),
+        )),
+    }
+}
+
+#[server(GetHistory, "/api")]
+pub async fn get_history(cx: Scope) -> Result<Vec<ListEvent>, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, user: CurrentUser| async move {
+            let list = List::from_user_id(db.get_ref(), user.id)
+                .await
+                .ok_or_else(|| {
+                    ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+                })?;
+
+            List::history(db.get_ref(), list.id).await.map_err(|_| {
+                ServerFnError::ServerError("Issue retrieving list history.".to_owned())
+            })
+        },
+    )
+    .await?
+}
+
+#[server(UndoChange, "/api")]
+pub async fn undo_change(cx: Scope, version: i32) -> Result<SaveOutcome, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use crate::live::ListHub;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, hub: Data<ListHub>, user: CurrentUser| async move {
+            let list = List::from_user_id(db.get_ref(), user.id.clone())
+                .await
+                .ok_or_else(|| {
+                    ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+                })?;
+
+            let result = List { version, ..list }
+                .undo(db.get_ref(), &user.id)
+                .await
+                .transpose()
+                .ok_or_else(|| ServerFnError::Args("There is no change to undo.".to_owned()))?;
+            save_outcome(result, &hub, &user.id, "Issue undoing change.")
+        },
+    )
     .await?
 }
 
+#[server(RedoChange, "/api")]
+pub async fn redo_change(cx: Scope, version: i32) -> Result<SaveOutcome, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use crate::live::ListHub;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, hub: Data<ListHub>, user: CurrentUser| async move {
+            let list = List::from_user_id(db.get_ref(), user.id.clone())
+                .await
+                .ok_or_else(|| {
+                    ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+                })?;
+
+            let result = List { version, ..list }
+                .redo(db.get_ref(), &user.id)
+                .await
+                .transpose()
+                .ok_or_else(|| ServerFnError::Args("There is no change to redo.".to_owned()))?;
+            save_outcome(result, &hub, &user.id, "Issue redoing change.")
+        },
+    )
+    .await?
+}
+
+#[server(RecordDonation, "/api")]
+pub async fn record_donation(
+    cx: Scope,
+    ein: i32,
+    amount: Decimal,
+    donated_on: NaiveDate,
+    receipt_reference: Option<String>,
+) -> Result<(), ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use crate::entity::donation::ActiveModel as ActiveDonationModel;
+    use actix_web::web::Data;
+    use sea_orm::*;
+
+    if !(1..=999_999_999).contains(&ein) {
+        return Err(ServerFnError::Args(
+            "EIN must be a positive 9 digit number.".to_owned(),
+        ));
+    }
+    if amount <= Decimal::ZERO {
+        return Err(ServerFnError::Args(
+            "Donation amount must be positive.".to_owned(),
+        ));
+    }
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, user: CurrentUser| async move {
+            let list = List::from_user_id(db.get_ref(), user.id)
+                .await
+                .ok_or_else(|| {
+                    ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+                })?;
+
+            ActiveDonationModel {
+                id: NotSet,
+                list_id: Set(list.id),
+                ein: Set(ein),
+                amount: Set(amount),
+                donated_on: Set(donated_on),
+                receipt_reference: Set(receipt_reference.filter(|reference| !reference.is_empty())),
+            }
+            .insert(db.get_ref())
+            .await
+            .map(|_| ())
+            .map_err(|_| ServerFnError::ServerError("Issue saving donation.".to_owned()))
+        },
+    )
+    .await?
+}
+
+#[server(EstimateTaxes, "/api")]
+pub async fn estimate_taxes(cx: Scope, profile: TaxProfile) -> Result<TaxEstimate, ServerFnError> {
+    use crate::clerk_middleware::current_user::CurrentUser;
+    use crate::tax::TaxTable;
+    use actix_web::web::Data;
+    use sea_orm::DatabaseConnection;
+
+    if profile.agi < Decimal::ZERO
+        || profile.other_itemized_deductions < Decimal::ZERO
+        || profile.private_foundation_giving < Decimal::ZERO
+    {
+        return Err(ServerFnError::Args(
+            "Income, deductions and giving must not be negative.".to_owned(),
+        ));
+    }
+
+    leptos_actix::extract(
+        cx,
+        move |db: Data<DatabaseConnection>, user: CurrentUser| async move {
+            let list = List::from_user_id(db.get_ref(), user.id)
+                .await
+                .ok_or_else(|| {
+                    ServerFnError::ServerError("Issue retrieving list from database.".to_owned())
+                })?;
+
+            Ok(list.estimate_taxes(&TaxTable::bundled(), &profile))
+        },
+    )
+    .await?
+}
+
+#[cfg(feature = "ssr")]
+fn import_issues_error(issues: Vec<ImportIssue>) -> ServerFnError {
+    ServerFnError::Args(
+        issues
+            .iter()
+            .map(|issue| format!("{}: {}", issue.location, issue.message))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    )
+}
+
 #[component]
 pub fn EnginePage(cx: Scope) -> impl IntoView {
     let app_state = expect_context::<AppState>(cx);
 
-    let load_list = create_resource(
+    create_resource(
         cx,
-        app_state.clerk,
-        // every time `count` changes, this will run
-        move |clerk| async move {
-            let clerk = if let Some(clerk) = clerk {
-                clerk
-            } else {
+        move || app_state.session.get(),
+        // every time the session changes, this will run
+        move |session| async move {
+            if session.is_none() {
                 return;
-            };
+            }
 
-            let user = if let Some(user) = clerk.user {
-                user
-            } else {
-                return;
+            if let Ok(list) = get_list(cx).await {
+                app_state.list.set(Some(list));
+            }
+        },
+    );
+
+    view! { cx,
+        <h1>"This is the engine page."</h1>
+        <LiveUpdates/>
+        <ListActions/>
+        <SaveConflict/>
+        <BudgetBreakdown/>
+        <TaxEstimator/>
+    }
+}
+
+/// Saves the list and undoes or redoes its last change. When the server refuses because the
+/// list changed since it was loaded, the stored list goes to `SaveConflict`.
+#[component]
+fn ListActions(cx: Scope) -> impl IntoView {
+    let app_state = expect_context::<AppState>(cx);
+
+    let show_outcome = move |outcome: Result<SaveOutcome, ServerFnError>| match outcome {
+        Ok(SaveOutcome::Saved(list)) => app_state.list.set(Some(list)),
+        Ok(SaveOutcome::Conflict(stored)) => app_state.conflict.set(Some(stored)),
+        Err(error) => log!("{error}"),
+    };
+    let version = move || {
+        app_state
+            .list
+            .with(|list| list.as_ref().map(|list| list.version))
+    };
+
+    let save = move |_| {
+        if let Some(list) = app_state.list.get() {
+            spawn_local(async move { show_outcome(save_list(cx, list).await) });
+        }
+    };
+    let undo = move |_| {
+        if let Some(version) = version() {
+            spawn_local(async move { show_outcome(undo_change(cx, version).await) });
+        }
+    };
+    let redo = move |_| {
+        if let Some(version) = version() {
+            spawn_local(async move { show_outcome(redo_change(cx, version).await) });
+        }
+    };
+
+    view! { cx,
+        <Show when=move || app_state.list.with(Option::is_some) fallback=|_| ()>
+            <ion-button on:click=save>"Save"</ion-button>
+            <ion-button fill="outline" on:click=undo>"Undo"</ion-button>
+            <ion-button fill="outline" on:click=redo>"Redo"</ion-button>
+        </Show>
+    }
+}
+
+/// Subscribes to the list's server-sent events and merges changes made in other tabs and
+/// devices into `AppState.list`.
+#[component]
+fn LiveUpdates(cx: Scope) -> impl IntoView {
+    let app_state = expect_context::<AppState>(cx);
+    let source = store_value(cx, None::<(EventSource, Closure<dyn Fn(MessageEvent)>)>);
+
+    let list_id = create_memo(cx, move |_| {
+        app_state
+            .list
+            .with(|list| list.as_ref().map(|list| list.id))
+    });
+
+    let close = move || {
+        source.try_update_value(|source| {
+            if let Some((source, _)) = source.take() {
+                source.close();
+            }
+        });
+    };
+
+    create_effect(cx, move |_| {
+        close();
+
+        let Some(list_id) = list_id.get() else {
+            return;
+        };
+        let Ok(events) = EventSource::new(&format!("/live/{list_id}")) else {
+            return;
+        };
+
+        let listener = Closure::wrap(Box::new(move |event: MessageEvent| {
+            let update = event
+                .data()
+                .as_string()
+                .and_then(|data| serde_json::from_str::<ListUpdate>(&data).ok());
+
+            if let Some(update) = update {
+                app_state.list.update(|list| {
+                    if let Some(list) = list {
+                        list.apply_update(&update);
+                    }
+                });
+            }
+        }) as Box<dyn Fn(MessageEvent)>);
+
+        if events
+            .add_event_listener_with_callback("list", listener.as_ref().unchecked_ref())
+            .is_ok()
+        {
+            source.set_value(Some((events, listener)));
+        } else {
+            events.close();
+        }
+    });
+
+    on_cleanup(cx, close);
+}
+
+/// Shown when a save lost to a concurrent change. Merging keeps the local edits that do not
+/// touch rows changed on the server, the merged list still has to be saved.
+#[component]
+fn SaveConflict(cx: Scope) -> impl IntoView {
+    let app_state = expect_context::<AppState>(cx);
+
+    let merge = move |_| {
+        if let Some(stored) = app_state.conflict.get() {
+            let merged = match app_state.list.get() {
+                Some(local) => local.merge_onto(&stored),
+                None => stored,
             };
+            app_state.list.set(Some(merged));
+            app_state.conflict.set(None);
+        }
+    };
+    let reload = move |_| {
+        if let Some(stored) = app_state.conflict.get() {
+            app_state.list.set(Some(stored));
+            app_state.conflict.set(None);
+        }
+    };
 
-            let list = get_list(cx, user.id).await?;
+    view! { cx,
+        <Show when=move || app_state.conflict.with(Option::is_some) fallback=|_| ()>
+            <ion-card color="warning">
+                <ion-card-content>
+                    "This list was changed somewhere else since you opened it."
+                    <ion-button on:click=merge>"Merge my changes"</ion-button>
+                    <ion-button fill="outline" on:click=reload>"Reload"</ion-button>
+                </ion-card-content>
+            </ion-card>
+        </Show>
+    }
+}
 
-            app_state.list.set(list);
+#[component]
+fn BudgetBreakdown(cx: Scope) -> impl IntoView {
+    let app_state = expect_context::<AppState>(cx);
+
+    let budget = create_memo(cx, move |_| {
+        app_state.list.with(|list| {
+            list.as_ref()
+                .map(|list| (list.budget.mode, list.process_list().budget))
+        })
+    });
+
+    view! { cx,
+        {move || match budget.get() {
+            Some((BudgetMode::Brackets, budget)) => view! { cx,
+                <table>
+                    <thead>
+                        <tr>
+                            <th>"Income"</th>
+                            <th>"Percent"</th>
+                            <th>"Income in bracket"</th>
+                            <th>"Donation"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {budget.bracket_breakdown.into_iter().map(|bracket| view! { cx,
+                            <tr>
+                                <td>{match bracket.ceiling {
+                                    Some(ceiling) => format!("${} - ${}", bracket.threshold, ceiling),
+                                    None => format!("${}+", bracket.threshold),
+                                }}</td>
+                                <td>{format!("{}%", bracket.percent * Decimal::ONE_HUNDRED)}</td>
+                                <td>{format!("${}", bracket.income_in_bracket.round_dp(2))}</td>
+                                <td>{format!("${}", bracket.donation_value.round_dp(2))}</td>
+                            </tr>
+                        }).collect_view(cx)}
+                    </tbody>
+                </table>
+                <p>"Total donation: $"{budget.donation_value.round_dp(2).to_string()}</p>
+            }.into_view(cx),
+            Some((_, budget)) => view! { cx,
+                <p>"Total donation: $"{budget.donation_value.round_dp(2).to_string()}</p>
+            }.into_view(cx),
+            None => ().into_view(cx),
+        }}
+    }
+}
+
+/// Estimates the tax saved by the planned giving. The income starts out as the budget's total
+/// value, the estimate is fetched again whenever the profile or the saved list changes.
+#[component]
+fn TaxEstimator(cx: Scope) -> impl IntoView {
+    let app_state = expect_context::<AppState>(cx);
+
+    let filing_status = create_rw_signal(cx, FilingStatus::Single);
+    let agi = create_rw_signal(cx, None::<Decimal>);
+    let other_itemized_deductions = create_rw_signal(cx, Decimal::ZERO);
+    let private_foundation_giving = create_rw_signal(cx, Decimal::ZERO);
+
+    let budget_income = create_memo(cx, move |_| {
+        app_state.list.with(|list| {
+            list.as_ref()
+                .map(|list| (list.version, list.budget.total_value))
+        })
+    });
+
+    let estimate = create_resource(
+        cx,
+        move || {
+            budget_income.get().map(|(version, total_value)| {
+                let profile = TaxProfile {
+                    filing_status: filing_status.get(),
+                    agi: agi.get().unwrap_or(total_value),
+                    other_itemized_deductions: other_itemized_deductions.get(),
+                    private_foundation_giving: private_foundation_giving.get(),
+                };
+                (version, profile)
+            })
+        },
+        move |source| async move {
+            match source {
+                Some((_, profile)) => estimate_taxes(cx, profile).await.ok(),
+                None => None,
+            }
         },
     );
 
+    // Keeps the last value typed that parses.
+    let decimal_input = move |signal: RwSignal<Decimal>| {
+        move |event| {
+            if let Ok(value) = event_target_value(&event).parse::<Decimal>() {
+                signal.set(value);
+            }
+        }
+    };
+    let set_agi = move |event| {
+        if let Ok(value) = event_target_value(&event).parse::<Decimal>() {
+            agi.set(Some(value));
+        }
+    };
+    let set_filing_status = move |event| {
+        filing_status.set(match event_target_value(&event).as_str() {
+            "married_filing_jointly" => FilingStatus::MarriedFilingJointly,
+            "married_filing_separately" => FilingStatus::MarriedFilingSeparately,
+            "head_of_household" => FilingStatus::HeadOfHousehold,
+            _ => FilingStatus::Single,
+        });
+    };
+
+    let dollars = |value: Decimal| format!("${}", value.round_dp(2));
+
     view! { cx,
-        <h1>"This is the engine page."</h1>
+        <Show when=move || budget_income.with(Option::is_some) fallback=|_| ()>
+            <h2>"Tax estimate"</h2>
+            <label>
+                "Filing status"
+                <select on:change=set_filing_status>
+                    <option value="single">"Single"</option>
+                    <option value="married_filing_jointly">"Married filing jointly"</option>
+                    <option value="married_filing_separately">"Married filing separately"</option>
+                    <option value="head_of_household">"Head of household"</option>
+                </select>
+            </label>
+            <label>
+                "Adjusted gross income"
+                <input
+                    type="number"
+                    min="0"
+                    prop:value=move || {
+                        agi.get()
+                            .or_else(|| budget_income.get().map(|(_, total_value)| total_value))
+                            .unwrap_or_default()
+                            .to_string()
+                    }
+                    on:change=set_agi
+                />
+            </label>
+            <label>
+                "Other itemized deductions"
+                <input
+                    type="number"
+                    min="0"
+                    prop:value=move || other_itemized_deductions.get().to_string()
+                    on:change=decimal_input(other_itemized_deductions)
+                />
+            </label>
+            <label>
+                "Giving to private foundations"
+                <input
+                    type="number"
+                    min="0"
+                    prop:value=move || private_foundation_giving.get().to_string()
+                    on:change=decimal_input(private_foundation_giving)
+                />
+            </label>
+
+            <Transition fallback=|| ()>
+                {move || estimate.read(cx).flatten().map(|estimate| view! { cx,
+                    <table>
+                        <tbody>
+                            <tr>
+                                <td>"Planned giving"</td>
+                                <td>{dollars(estimate.giving)}</td>
+                            </tr>
+                            <tr>
+                                <td>"Deductible this year"</td>
+                                <td>{dollars(estimate.deductible_giving)}</td>
+                            </tr>
+                            <tr>
+                                <td>"Carried over to later years"</td>
+                                <td>{dollars(estimate.carryover)}</td>
+                            </tr>
+                            <tr>
+                                <td>{if estimate.itemize { "Itemized deduction" } else { "Standard deduction" }}</td>
+                                <td>{dollars(if estimate.itemize {
+                                    estimate.itemized.deduction
+                                } else {
+                                    estimate.standard.deduction
+                                })}</td>
+                            </tr>
+                            <tr>
+                                <td>"Marginal rate"</td>
+                                <td>{format!("{}%", estimate.marginal_rate * Decimal::ONE_HUNDRED)}</td>
+                            </tr>
+                            <tr>
+                                <td>"Tax saved"</td>
+                                <td>{dollars(estimate.tax_savings)}</td>
+                            </tr>
+                            <tr>
+                                <td>"After-tax cost"</td>
+                                <td>{dollars(estimate.after_tax_cost)}</td>
+                            </tr>
+                        </tbody>
+                    </table>
+                    <p>{if estimate.bunching.savings > Decimal::ZERO {
+                        format!(
+                            "Giving two years' worth at once in {} would save {} over the two years.",
+                            estimate.tax_year,
+                            dollars(estimate.bunching.savings),
+                        )
+                    } else {
+                        "Giving two years' worth at once would not lower your tax.".to_owned()
+                    }}</p>
+                })}
+            </Transition>
+        </Show>
     }
 }
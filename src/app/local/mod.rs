@@ -0,0 +1,106 @@
+use leptos::*;
+
+use super::{
+    auth::{is_logged_in, AuthSession},
+    AppState,
+};
+
+/// Signs in as `user_id` without a password. Only routed when the server runs the local
+/// provider.
+#[server(LocalSignIn, "/api")]
+pub async fn local_sign_in(cx: Scope, user_id: String) -> Result<AuthSession, ServerFnError> {
+    use crate::clerk_middleware::provider::LocalProvider;
+    use actix_web::{cookie::Cookie, web::Data};
+
+    let user_id = user_id.trim().to_owned();
+    if user_id.is_empty() {
+        return Err(ServerFnError::Args("User id must not be empty.".to_owned()));
+    }
+
+    let (token, claims) =
+        leptos_actix::extract(cx, move |provider: Data<LocalProvider>| async move {
+            let claims = provider.claims(&user_id);
+            provider.sign(&claims).map(|token| (token, claims))
+        })
+        .await?
+        .map_err(|_| ServerFnError::ServerError("Issue signing session.".to_owned()))?;
+
+    set_session_cookie(
+        cx,
+        Cookie::build("__session", token)
+            .path("/")
+            .http_only(true)
+            .same_site(actix_web::cookie::SameSite::Lax)
+            .finish(),
+    )?;
+
+    Ok(AuthSession {
+        user_id: claims.sub,
+        session_id: claims.sid,
+    })
+}
+
+#[server(LocalSignOut, "/api")]
+pub async fn local_sign_out(cx: Scope) -> Result<(), ServerFnError> {
+    use actix_web::cookie::Cookie;
+
+    let mut cookie = Cookie::named("__session");
+    cookie.set_path("/");
+    cookie.make_removal();
+
+    set_session_cookie(cx, cookie)
+}
+
+#[cfg(feature = "ssr")]
+fn set_session_cookie(
+    cx: Scope,
+    cookie: actix_web::cookie::Cookie<'_>,
+) -> Result<(), ServerFnError> {
+    use actix_web::http::header::{HeaderValue, SET_COOKIE};
+
+    let value = HeaderValue::from_str(&cookie.to_string())
+        .map_err(|_| ServerFnError::ServerError("Issue setting session cookie.".to_owned()))?;
+    expect_context::<leptos_actix::ResponseOptions>(cx).insert_header(SET_COOKIE, value);
+
+    Ok(())
+}
+
+/// Picks up the session cookie left by an earlier local sign in.
+#[component]
+pub fn LocalAuth(cx: Scope) -> impl IntoView {
+    let app_state = expect_context::<AppState>(cx);
+
+    create_effect(cx, move |_| {
+        spawn_local(async move {
+            if let Ok(Some(session)) = is_logged_in(cx).await {
+                app_state.session.set(Some(session.into()));
+            }
+        });
+    });
+}
+
+/// Asks for a user id and signs in as them.
+pub fn sign_in(cx: Scope) {
+    let app_state = expect_context::<AppState>(cx);
+
+    let Ok(Some(user_id)) = window().prompt_with_message("Sign in as user id") else {
+        return;
+    };
+
+    spawn_local(async move {
+        match local_sign_in(cx, user_id).await {
+            Ok(session) => app_state.session.set(Some(session)),
+            Err(error) => log!("{error}"),
+        }
+    });
+}
+
+pub fn sign_out(cx: Scope) {
+    let app_state = expect_context::<AppState>(cx);
+
+    spawn_local(async move {
+        if local_sign_out(cx).await.is_ok() {
+            app_state.session.set(None);
+        }
+    });
+}
@@ -0,0 +1,59 @@
+//! Loads the IRS Publication 78 data file into the nonprofit table, for the deductibility
+//! column of the tax year report. Run it again with a newer file to update it.
+//!
+//! `cargo run --bin load_nonprofits --features ssr -- <data-download-pub78.txt>`
+
+#[cfg(feature = "ssr")]
+#[actix_web::main]
+async fn main() {
+    use leptos_start::config::AppConfig;
+    use leptos_start::database::{connect_database, nonprofits::load_publication_78};
+    use sea_orm::TransactionTrait;
+    use std::{fs::File, io::BufReader, process::exit};
+
+    const USAGE: &str = "Usage: load_nonprofits <data-download-pub78.txt>";
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [path] = args.as_slice() else {
+        eprintln!("{USAGE}");
+        exit(2);
+    };
+    let file = File::open(path).unwrap_or_else(|error| {
+        eprintln!("Unable to open {path}: {error}");
+        exit(1);
+    });
+
+    let config = AppConfig::load().unwrap_or_else(|errors| {
+        eprintln!("{errors}");
+        exit(1);
+    });
+    let db = connect_database(&config.database).await;
+
+    let result = async {
+        let txn = db.begin().await?;
+        let summary = load_publication_78(&txn, BufReader::new(file)).await?;
+        txn.commit().await?;
+        Ok::<_, sea_orm::DbErr>(summary)
+    }
+    .await;
+
+    match result {
+        Ok(summary) => {
+            println!("Loaded {} organizations.", summary.loaded);
+            if !summary.skipped.is_empty() {
+                println!(
+                    "Skipped {} lines without an EIN or name: {:?}",
+                    summary.skipped.len(),
+                    summary.skipped
+                );
+            }
+        }
+        Err(error) => {
+            eprintln!("Loading nonprofits failed: {error}");
+            exit(1);
+        }
+    }
+}
+
+#[cfg(not(feature = "ssr"))]
+fn main() {}
@@ -0,0 +1,58 @@
+//! Applies or rolls back database migrations with the server's configuration.
+//!
+//! `cargo run --bin migrate --features ssr -- <up [n] | down [n] | status | fresh>`
+
+#[cfg(feature = "ssr")]
+#[actix_web::main]
+async fn main() {
+    use leptos_start::config::AppConfig;
+    use leptos_start::database::{connect_database, migrate, migration_status, MigrateCommand};
+    use std::process::exit;
+
+    const USAGE: &str = "Usage: migrate <up [n] | down [n] | status | fresh>";
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let steps = match args.get(1).map(|steps| steps.parse::<u32>()) {
+        None => None,
+        Some(Ok(steps)) => Some(steps),
+        Some(Err(_)) => {
+            eprintln!("{USAGE}");
+            exit(2);
+        }
+    };
+    let command = match args.first().map(String::as_str) {
+        Some("up") => Some(MigrateCommand::Up(steps)),
+        Some("down") => Some(MigrateCommand::Down(steps)),
+        Some("fresh") => Some(MigrateCommand::Fresh),
+        Some("status") => None,
+        _ => {
+            eprintln!("{USAGE}");
+            exit(2);
+        }
+    };
+
+    let config = AppConfig::load().unwrap_or_else(|errors| {
+        eprintln!("{errors}");
+        exit(1);
+    });
+    let db = connect_database(&config.database).await;
+
+    let result = match command {
+        Some(command) => migrate(&db, &config.database, command).await,
+        None => migration_status(&db, &config.database)
+            .await
+            .map(|migrations| {
+                for (name, status) in migrations {
+                    println!("{status:<8} {name}");
+                }
+            }),
+    };
+
+    if let Err(error) = result {
+        eprintln!("Migration failed: {error}");
+        exit(1);
+    }
+}
+
+#[cfg(not(feature = "ssr"))]
+fn main() {}
@@ -0,0 +1,31 @@
+use actix_web::{dev::Payload, Error, FromRequest, HttpMessage, HttpRequest};
+use std::future::{ready, Ready};
+
+use super::{AuthError, ClerkJwt};
+
+/// The Clerk user whose token `ClerkMiddleware` verified for this request. Server functions
+/// get it through `leptos_actix::extract` instead of trusting a user id sent by the client.
+#[derive(Clone, Debug)]
+pub struct CurrentUser {
+    /// Clerk user id, the token's `sub` claim.
+    pub id: String,
+    pub session_id: String,
+}
+
+impl FromRequest for CurrentUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let user = req
+            .extensions()
+            .get::<ClerkJwt>()
+            .map(|claims| CurrentUser {
+                id: claims.sub.clone(),
+                session_id: claims.sid.clone(),
+            })
+            .ok_or_else(|| AuthError::MissingToken.into());
+
+        ready(user)
+    }
+}
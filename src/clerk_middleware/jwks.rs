@@ -0,0 +1,181 @@
+use jsonwebtoken::jwk::JwkSet;
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Where the key set comes from and how long a fetched copy is trusted.
+#[derive(Clone, Debug)]
+pub struct JwksCacheConfig {
+    pub url: String,
+    /// Sent as a bearer token, Clerk's backend API requires the secret key.
+    pub secret_key: Option<String>,
+    /// How long a fetched key set is used without asking Clerk again.
+    pub ttl: Duration,
+    /// Minimum time between fetches caused by tokens signed with an unknown `kid`, so forged
+    /// tokens cannot make every request hit Clerk.
+    pub min_refresh_interval: Duration,
+    /// How long past `ttl` an old key set keeps being served while Clerk cannot be reached.
+    pub max_stale: Duration,
+    pub timeout: Duration,
+}
+
+impl JwksCacheConfig {
+    pub fn clerk(secret_key: String) -> Self {
+        Self {
+            url: "https://api.clerk.com/v1/jwks".to_owned(),
+            secret_key: Some(secret_key),
+            ttl: Duration::from_secs(60 * 60),
+            min_refresh_interval: Duration::from_secs(30),
+            max_stale: Duration::from_secs(24 * 60 * 60),
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Clerk's JWKS shared by every worker. Build it once in `main` and hand clones to each
+/// `ClerkMiddleware`, they all read and refresh the same key set.
+#[derive(Clone)]
+pub struct JwksCache {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    config: JwksCacheConfig,
+    client: reqwest::Client,
+    state: Mutex<State>,
+    /// Held while fetching so concurrent misses share one request.
+    refresh: tokio::sync::Mutex<()>,
+}
+
+#[derive(Default)]
+struct State {
+    jwks: Option<Arc<JwkSet>>,
+    fetched_at: Option<Instant>,
+    last_attempt: Option<Instant>,
+}
+
+enum Plan {
+    Use(Arc<JwkSet>),
+    /// Serve the stale set now and refresh in the background.
+    Revalidate(Arc<JwkSet>),
+    /// Fetch now, falling back to the given set if that fails.
+    Fetch(Option<Arc<JwkSet>>),
+}
+
+impl JwksCache {
+    pub fn new(config: JwksCacheConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .expect("Unable to build JWKS http client.");
+
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                client,
+                state: Mutex::new(State::default()),
+                refresh: tokio::sync::Mutex::new(()),
+            }),
+        }
+    }
+
+    /// Returns a key set to verify a token signed with `kid`. Only fails when no usable key
+    /// set is cached and Clerk cannot be reached.
+    pub async fn key_set(&self, kid: Option<&str>) -> reqwest::Result<Arc<JwkSet>> {
+        match self.plan(kid) {
+            Plan::Use(jwks) => Ok(jwks),
+            Plan::Revalidate(jwks) => {
+                let cache = self.clone();
+                actix_web::rt::spawn(async move {
+                    if let Err(error) = cache.refresh().await {
+                        log::warn!("Unable to refresh JWKS, serving the cached keys: {error}");
+                    }
+                });
+                Ok(jwks)
+            }
+            Plan::Fetch(fallback) => match (self.refresh().await, fallback) {
+                (Ok(jwks), _) => Ok(jwks),
+                (Err(error), Some(jwks)) => {
+                    log::warn!("Unable to refresh JWKS, serving the cached keys: {error}");
+                    Ok(jwks)
+                }
+                (Err(error), None) => Err(error),
+            },
+        }
+    }
+
+    fn plan(&self, kid: Option<&str>) -> Plan {
+        let config = &self.inner.config;
+        let state = self.state();
+        let now = Instant::now();
+
+        let (jwks, fetched_at) = match (&state.jwks, state.fetched_at) {
+            (Some(jwks), Some(fetched_at)) => (jwks.clone(), fetched_at),
+            _ => return Plan::Fetch(None),
+        };
+
+        let age = now.duration_since(fetched_at);
+        let usable = age < config.ttl + config.max_stale;
+        let knows_kid = kid.map_or(true, |kid| jwks.find(kid).is_some());
+
+        if !knows_kid {
+            let may_refresh = state.last_attempt.map_or(true, |at| {
+                now.duration_since(at) >= config.min_refresh_interval
+            });
+
+            return match (may_refresh, usable) {
+                (true, true) => Plan::Fetch(Some(jwks)),
+                (true, false) => Plan::Fetch(None),
+                // The token fails to verify against the cached set, which is what we want.
+                (false, _) => Plan::Use(jwks),
+            };
+        }
+
+        if age < config.ttl {
+            Plan::Use(jwks)
+        } else if usable {
+            Plan::Revalidate(jwks)
+        } else {
+            Plan::Fetch(None)
+        }
+    }
+
+    async fn refresh(&self) -> reqwest::Result<Arc<JwkSet>> {
+        let started = Instant::now();
+        let _refreshing = self.inner.refresh.lock().await;
+
+        // Another request refreshed the set while this one waited for the lock.
+        {
+            let state = self.state();
+            if let (Some(jwks), Some(fetched_at)) = (&state.jwks, state.fetched_at) {
+                if fetched_at >= started {
+                    return Ok(jwks.clone());
+                }
+            }
+        }
+
+        self.state().last_attempt = Some(Instant::now());
+        let jwks = Arc::new(self.fetch().await?);
+
+        let mut state = self.state();
+        state.jwks = Some(jwks.clone());
+        state.fetched_at = Some(Instant::now());
+        Ok(jwks)
+    }
+
+    async fn fetch(&self) -> reqwest::Result<JwkSet> {
+        let config = &self.inner.config;
+
+        let mut request = self.inner.client.get(&config.url);
+        if let Some(secret_key) = &config.secret_key {
+            request = request.bearer_auth(secret_key);
+        }
+
+        request.send().await?.error_for_status()?.json().await
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.inner.state.lock().expect("JWKS cache lock poisoned.")
+    }
+}
@@ -1,30 +1,42 @@
 // From rust_clerk
 
+pub mod current_user;
+pub mod jwks;
+pub mod provider;
+pub mod routes;
+
 use actix_web::{
     body::EitherBody,
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    error::Error,
-    http::header::HeaderValue,
-    HttpResponse,
-};
-use clerk_rs::{
-    apis::jwks_api::{Jwks, JwksModel},
-    clerk::Clerk,
-    ClerkConfiguration,
+    error::{Error, ResponseError},
+    http::{
+        header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE},
+        StatusCode,
+    },
+    HttpMessage, HttpRequest, HttpResponse,
 };
 use futures_util::future::LocalBoxFuture;
 use jsonwebtoken::{
-    decode, decode_header, errors::Error as jwtError, Algorithm, DecodingKey, Header, Validation,
+    decode, decode_header,
+    errors::{Error as jwtError, ErrorKind},
+    jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet},
+    Algorithm, DecodingKey, Header, Validation,
 };
 use serde::{Deserialize, Serialize};
 use std::{
+    fmt,
     future::{ready, Ready},
     rc::Rc,
+    sync::Arc,
 };
 
-#[derive(Debug, Serialize, Deserialize)]
+use provider::AuthProvider;
+use routes::{Access, RouteMatcher};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ClerkJwt {
-    pub azp: String,
+    /// The origin the token was issued for, missing on tokens not minted by a browser.
+    pub azp: Option<String>,
     pub exp: i32,
     pub iat: i32,
     pub iss: String,
@@ -33,98 +45,260 @@ pub struct ClerkJwt {
     pub sub: String,
 }
 
+/// Which claims a Clerk token must carry besides a valid signature and `exp`.
+#[derive(Clone, Debug)]
+pub struct JwtValidation {
+    /// The Clerk frontend API url, e.g. `https://clerk.example.com`. Not checked when `None`.
+    pub issuer: Option<String>,
+    /// Origins allowed in `azp`. Not checked when `None`, otherwise tokens without `azp` are
+    /// rejected.
+    pub authorized_parties: Option<Vec<String>>,
+    /// Seconds of clock skew allowed on `exp` and `nbf`.
+    pub leeway: u64,
+}
+
+impl Default for JwtValidation {
+    fn default() -> Self {
+        Self {
+            issuer: None,
+            authorized_parties: None,
+            leeway: 5,
+        }
+    }
+}
+
+/// Why a request could not be authenticated.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuthError {
+    MissingToken,
+    MalformedToken,
+    /// The token has no `kid`, or no JWK matches it.
+    UnknownKey,
+    /// The JWK is symmetric or uses an algorithm other than RS256, ES256 or EdDSA.
+    UnsupportedKey,
+    InvalidKey,
+    /// The token's `alg` does not match its JWK.
+    AlgorithmMismatch,
+    InvalidSignature,
+    Expired,
+    NotYetValid,
+    InvalidIssuer,
+    UnauthorizedParty,
+    /// Authorized parties are configured but the token has no `azp`.
+    MissingParty,
+    /// The key set could not be fetched from Clerk.
+    JwksUnavailable,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            AuthError::MissingToken => "No jwt found on the request.",
+            AuthError::MalformedToken => "The jwt could not be parsed.",
+            AuthError::UnknownKey => "The jwt was signed with an unknown key.",
+            AuthError::UnsupportedKey => "The jwt was signed with an unsupported key.",
+            AuthError::InvalidKey => "The signing key is invalid.",
+            AuthError::AlgorithmMismatch => "The jwt algorithm does not match its key.",
+            AuthError::InvalidSignature => "The jwt signature is invalid.",
+            AuthError::Expired => "The jwt has expired.",
+            AuthError::NotYetValid => "The jwt is not valid yet.",
+            AuthError::InvalidIssuer => "The jwt was issued by an unknown issuer.",
+            AuthError::UnauthorizedParty => "The jwt was issued for another origin.",
+            AuthError::MissingParty => "The jwt does not name the origin it was issued for.",
+            AuthError::JwksUnavailable => "Could not fetch JWKS.",
+        };
+        write!(f, "{message}")
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+impl AuthError {
+    /// Stable identifier for clients, the message may change.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::MissingToken => "missing_token",
+            AuthError::MalformedToken => "malformed_token",
+            AuthError::UnknownKey => "unknown_key",
+            AuthError::UnsupportedKey => "unsupported_key",
+            AuthError::InvalidKey => "invalid_key",
+            AuthError::AlgorithmMismatch => "algorithm_mismatch",
+            AuthError::InvalidSignature => "invalid_signature",
+            AuthError::Expired => "token_expired",
+            AuthError::NotYetValid => "token_not_yet_valid",
+            AuthError::InvalidIssuer => "invalid_issuer",
+            AuthError::UnauthorizedParty => "unauthorized_party",
+            AuthError::MissingParty => "missing_party",
+            AuthError::JwksUnavailable => "jwks_unavailable",
+        }
+    }
+}
+
+/// JSON body of an authentication error response.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuthErrorBody {
+    pub code: String,
+    pub message: String,
+}
+
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::JwksUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if self.status_code() == StatusCode::UNAUTHORIZED {
+            response.insert_header((WWW_AUTHENTICATE, "Bearer"));
+        }
+
+        response.json(AuthErrorBody {
+            code: self.code().to_owned(),
+            message: self.to_string(),
+        })
+    }
+}
+
+impl From<jwtError> for AuthError {
+    fn from(error: jwtError) -> Self {
+        match error.kind() {
+            ErrorKind::ExpiredSignature => AuthError::Expired,
+            ErrorKind::ImmatureSignature => AuthError::NotYetValid,
+            ErrorKind::InvalidIssuer => AuthError::InvalidIssuer,
+            ErrorKind::InvalidSignature => AuthError::InvalidSignature,
+            ErrorKind::InvalidAlgorithm => AuthError::AlgorithmMismatch,
+            ErrorKind::InvalidRsaKey(_)
+            | ErrorKind::InvalidEcdsaKey
+            | ErrorKind::InvalidKeyFormat => AuthError::InvalidKey,
+            _ => AuthError::MalformedToken,
+        }
+    }
+}
+
 /// Extract token kid from a jwt passed as header to an actix-web endpoint
 pub fn token_kid(jwt: String) -> Result<Header, jwtError> {
-    let header = decode_header(&jwt);
-    header
+    decode_header(&jwt)
 }
 
-/// Validate a jwt using a jwks set
-pub fn validate_jwt(token: &str, jwks: JwksModel) -> Result<(bool, ClerkJwt), bool> {
-    let parsed_jwt = token.replace("Bearer ", "");
-
-    // If we were not able to parse the kid field we want to output an invalid case...
-    let kid = match token_kid(parsed_jwt.to_owned()) {
-        Ok(val) => val.kid,
-        Err(_) => {
-            return Err(false);
+/// The algorithm a JWK verifies, from its `alg` or else its key type and curve.
+fn jwk_algorithm(jwk: &Jwk) -> Result<Algorithm, AuthError> {
+    let algorithm = match (&jwk.algorithm, jwk.common.algorithm) {
+        (AlgorithmParameters::OctetKey(_), _) => return Err(AuthError::UnsupportedKey),
+        (_, Some(algorithm)) => algorithm,
+        (AlgorithmParameters::RSA(_), None) => Algorithm::RS256,
+        (AlgorithmParameters::EllipticCurve(params), None)
+            if params.curve == EllipticCurve::P256 =>
+        {
+            Algorithm::ES256
         }
+        (AlgorithmParameters::OctetKeyPair(params), None)
+            if params.curve == EllipticCurve::Ed25519 =>
+        {
+            Algorithm::EdDSA
+        }
+        _ => return Err(AuthError::UnsupportedKey),
     };
 
-    let jwk = if let Some(kid) = kid.as_ref() {
-        jwks.keys.iter().find(|k| &k.kid == kid)
-    } else {
-        jwks.keys.first()
-    };
+    match (algorithm, &jwk.algorithm) {
+        (Algorithm::RS256, AlgorithmParameters::RSA(_))
+        | (Algorithm::ES256, AlgorithmParameters::EllipticCurve(_))
+        | (Algorithm::EdDSA, AlgorithmParameters::OctetKeyPair(_)) => Ok(algorithm),
+        _ => Err(AuthError::UnsupportedKey),
+    }
+}
 
-    // Check to see if we found a valid jwk key with the token kid
-    if let Some(j) = jwk {
-        match j.alg.as_str() {
-            // Currently, clerk only supports Rs256 by default (could be wrong here -- might need to look into this)
-            "RS256" => {
-                let decoding_key = DecodingKey::from_rsa_components(&j.n, &j.e).unwrap();
-                let mut validation = Validation::new(Algorithm::RS256);
-                validation.validate_exp = true;
-                let decoded_token = decode::<ClerkJwt>(&parsed_jwt, &decoding_key, &validation);
-
-                return match decoded_token {
-                    Ok(token) => Ok((true, token.claims)),
-                    _ => Err(false),
-                };
-            }
-            _ => unreachable!("this should be a RSA"),
+/// Validate a jwt using a jwks set
+pub fn validate_jwt(
+    token: &str,
+    jwks: &JwkSet,
+    rules: &JwtValidation,
+) -> Result<ClerkJwt, AuthError> {
+    let header = token_kid(token.to_owned()).map_err(|_| AuthError::MalformedToken)?;
+
+    let jwk = header
+        .kid
+        .as_deref()
+        .and_then(|kid| jwks.find(kid))
+        .ok_or(AuthError::UnknownKey)?;
+
+    let algorithm = jwk_algorithm(jwk)?;
+    let decoding_key = DecodingKey::from_jwk(jwk).map_err(|_| AuthError::InvalidKey)?;
+
+    decode_claims(token, &decoding_key, algorithm, rules)
+}
+
+/// Checks the signature of a jwt with a known key, then its claims against `rules`.
+pub fn decode_claims(
+    token: &str,
+    key: &DecodingKey,
+    algorithm: Algorithm,
+    rules: &JwtValidation,
+) -> Result<ClerkJwt, AuthError> {
+    let mut validation = Validation::new(algorithm);
+    validation.leeway = rules.leeway;
+    validation.validate_exp = true;
+    validation.validate_nbf = true;
+    validation.set_required_spec_claims(&["exp", "nbf", "iss", "sub"]);
+    if let Some(issuer) = &rules.issuer {
+        validation.set_issuer(&[issuer]);
+    }
+
+    let claims = decode::<ClerkJwt>(token, key, &validation)?.claims;
+
+    if let Some(parties) = &rules.authorized_parties {
+        let azp = claims.azp.as_ref().ok_or(AuthError::MissingParty)?;
+        if !parties.contains(azp) {
+            return Err(AuthError::UnauthorizedParty);
         }
-    // In the event that a matching jwk was not found we want to output a false result (showing that the jwt was invalid)
-    } else {
-        return Err(false);
     }
+
+    Ok(claims)
 }
 
-/// Authorize a actix-web route given a `clerk_client` and a valid service request to an actix-web endpoint
+/// Authorize a actix-web request given the configured auth `provider`. Shared by the middleware
+/// and server functions that check a session without requiring one.
 pub async fn clerk_authorize(
-    req: &ServiceRequest,
-    clerk_client: &Clerk,
-) -> Result<(bool, ClerkJwt), HttpResponse> {
-    // Get our jwks from Clerk.dev
-    let jwks = match Jwks::get_jwks(clerk_client).await {
-        Ok(val) => val,
-        Err(_) => {
-            return Err(HttpResponse::InternalServerError().json("Error: Could not fetch JWKS!"))
-        }
-    };
-
+    req: &HttpRequest,
+    provider: &dyn AuthProvider,
+) -> Result<ClerkJwt, AuthError> {
     // Parse the request headers
-    let access_token: String = match req.cookie("__session") {
-        Some(val) => val.value().to_owned(),
-        None => {
-            return Err(HttpResponse::Unauthorized()
-                .json("Error: No jwt cookie found on the request payload!"))
-        }
-    };
+    let access_token = request_token(req).ok_or(AuthError::MissingToken)?;
 
     // Finally, check if the jwt is valid...
-    match validate_jwt(&access_token, jwks) {
-        Ok(val) => Ok(val),
-        Err(_) => return Err(HttpResponse::Unauthorized().json("Error: Invalid JWT!")),
-    }
+    provider.verify(&access_token).await
+}
+
+/// The jwt of a request. An `Authorization: Bearer` header, sent by scripts and mobile clients,
+/// takes precedence over the `__session` cookie Clerk sets in browsers.
+pub fn request_token(req: &HttpRequest) -> Option<String> {
+    let bearer = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_owned())
+        .filter(|token| !token.is_empty());
+
+    bearer.or_else(|| req.cookie("__session").map(|val| val.value().to_owned()))
 }
 
 pub fn parse_cookies(req: &ServiceRequest) -> Option<&HeaderValue> {
     req.headers().get("cookie")
 }
 
-/// Actix-web middleware for protecting a http endpoint with Cerk.dev
+/// Actix-web middleware for protecting a http endpoint with Cerk.dev, or another `AuthProvider`
+#[derive(Clone)]
 pub struct ClerkMiddleware {
-    pub clerk_config: ClerkConfiguration,
-    pub routes: Option<Vec<String>>,
+    pub provider: Arc<dyn AuthProvider>,
+    pub routes: RouteMatcher,
 }
 
 impl ClerkMiddleware {
-    pub fn new(config: ClerkConfiguration, routes: Option<Vec<String>>) -> Self {
-        Self {
-            clerk_config: config,
-            routes,
-        }
+    pub fn new(provider: Arc<dyn AuthProvider>, routes: RouteMatcher) -> Self {
+        Self { provider, routes }
     }
 }
 
@@ -143,16 +317,16 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         ready(Ok(ClerkMiddlewareService {
             service: Rc::new(service),
-            config: self.clerk_config.clone(),
-            routes: self.routes.clone(),
+            provider: self.provider.clone(),
+            routes: Rc::new(self.routes.clone()),
         }))
     }
 }
 
 pub struct ClerkMiddlewareService<S> {
     service: Rc<S>,
-    config: ClerkConfiguration,
-    routes: Option<Vec<String>>,
+    provider: Arc<dyn AuthProvider>,
+    routes: Rc<RouteMatcher>,
 }
 
 impl<S: 'static, B> Service<ServiceRequest> for ClerkMiddlewareService<S>
@@ -168,58 +342,35 @@ where
     forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        // Initialize our Clerk client with the newly created configuration
-        let client = Clerk::new(self.config.clone());
+        let provider = self.provider.clone();
 
         let svc = self.service.clone();
 
-        // We want to skip running the validator if we are not able to find a matching path from the listed valid paths provided by the user
-        match self.routes.clone() {
-            Some(route_matches) => {
-                // If the user only wants to apply authentication to a select amount of routes, we handle that logic here
-                let path = req.path();
-                // Check if the path was NOT contained inside of the routes specified by the user...
-                if !route_matches.contains(&path.to_owned()) {
-                    // Since the path was not inside of the listed routes we want to trigger an early exit
-                    return Box::pin(async move {
-                        let res = svc.call(req).await?;
-                        return Ok(res.map_into_left_body());
-                    });
-                }
-            }
-            // Since we did find a matching route we can simply do nothing here and start the actual auth logic...
-            None => {}
+        // We want to skip running the validator if the first matching route rule makes the request public
+        if self.routes.access(req.method(), req.path()) == Access::Public {
+            return Box::pin(async move {
+                let res = svc.call(req).await?;
+                Ok(res.map_into_left_body())
+            });
         }
 
         Box::pin(async move {
             // Check if the request is authenticated
-            let is_authed = clerk_authorize(&req, &client).await;
+            let is_authed = clerk_authorize(req.request(), provider.as_ref()).await;
 
             match is_authed {
-                // If we got a boolean response then lets check if it was either true or false
-                Ok(val) => match val.0 {
-                    // If it was true then we have authed request and can pass the user onto the next body
-                    true => {
-                        let res = svc.call(req).await?;
-                        return Ok(res.map_into_left_body());
-                    }
-                    // If it was false we want to throw an unauthed error
-                    false => {
-                        return Ok(ServiceResponse::new(
-                            req.into_parts().0,
-                            HttpResponse::Unauthorized()
-                                .body("Unauthorized. All requests must contain a valid Clerk jwt.")
-                                .map_into_right_body(),
-                        ));
-                    }
-                },
-                // Output any other errors thrown fromn the clerk_authorize function
-                Err(e) => {
-                    return Ok(ServiceResponse::new(
-                        req.into_parts().0,
-                        e.map_into_right_body(),
-                    ));
+                // If the token was valid we have an authed request and can pass the user onto the next body
+                Ok(claims) => {
+                    // Keep the claims for the `CurrentUser` extractor
+                    req.extensions_mut().insert(claims);
+                    let res = svc.call(req).await?;
+                    Ok(res.map_into_left_body())
                 }
+                // Otherwise output the reason the clerk_authorize function rejected it
+                Err(e) => Ok(ServiceResponse::new(
+                    req.into_parts().0,
+                    e.error_response().map_into_right_body(),
+                )),
             }
         })
     }
@@ -0,0 +1,135 @@
+use futures_util::future::LocalBoxFuture;
+use jsonwebtoken::{
+    encode, errors::Result as JwtResult, Algorithm, DecodingKey, EncodingKey, Header,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{
+    decode_claims, jwks::JwksCache, token_kid, validate_jwt, AuthError, ClerkJwt, JwtValidation,
+};
+use crate::{app::auth::AuthProviderKind, config::LocalKey};
+
+/// Verifies the tokens `ClerkMiddleware` finds on requests. Built once in `main` and shared by
+/// every worker.
+pub trait AuthProvider: Send + Sync {
+    fn kind(&self) -> AuthProviderKind;
+
+    fn verify<'a>(&'a self, token: &'a str) -> LocalBoxFuture<'a, Result<ClerkJwt, AuthError>>;
+}
+
+/// Tokens issued by Clerk, checked against its cached JWKS.
+pub struct ClerkProvider {
+    pub jwks: JwksCache,
+    pub validation: JwtValidation,
+}
+
+impl AuthProvider for ClerkProvider {
+    fn kind(&self) -> AuthProviderKind {
+        AuthProviderKind::Clerk
+    }
+
+    fn verify<'a>(&'a self, token: &'a str) -> LocalBoxFuture<'a, Result<ClerkJwt, AuthError>> {
+        Box::pin(async move {
+            // Get the keys for the token's kid, Clerk.dev is only asked when the cache cannot answer
+            let kid = token_kid(token.to_owned())
+                .map_err(|_| AuthError::MalformedToken)?
+                .kid;
+            let jwks = self
+                .jwks
+                .key_set(kid.as_deref())
+                .await
+                .map_err(|_| AuthError::JwksUnavailable)?;
+
+            validate_jwt(token, &jwks, &self.validation)
+        })
+    }
+}
+
+/// Issues and verifies its own tokens for offline development and tests. Never use it in
+/// production, anyone who can reach the sign in server function gets a session.
+pub struct LocalProvider {
+    algorithm: Algorithm,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    validation: JwtValidation,
+    ttl: Duration,
+}
+
+impl LocalProvider {
+    pub const ISSUER: &'static str = "local";
+
+    /// Signs with a shared secret.
+    pub fn hs256(secret: &[u8]) -> Self {
+        Self::new(
+            Algorithm::HS256,
+            EncodingKey::from_secret(secret),
+            DecodingKey::from_secret(secret),
+        )
+    }
+
+    /// Signs with a dev RSA keypair in PEM format.
+    pub fn rs256(private_pem: &[u8], public_pem: &[u8]) -> JwtResult<Self> {
+        Ok(Self::new(
+            Algorithm::RS256,
+            EncodingKey::from_rsa_pem(private_pem)?,
+            DecodingKey::from_rsa_pem(public_pem)?,
+        ))
+    }
+
+    pub fn from_key(key: &LocalKey) -> JwtResult<Self> {
+        match key {
+            LocalKey::Secret(secret) => Ok(Self::hs256(secret.as_bytes())),
+            LocalKey::Rsa {
+                private_pem,
+                public_pem,
+            } => Self::rs256(private_pem, public_pem),
+        }
+    }
+
+    fn new(algorithm: Algorithm, encoding: EncodingKey, decoding: DecodingKey) -> Self {
+        Self {
+            algorithm,
+            encoding,
+            decoding,
+            validation: JwtValidation {
+                issuer: Some(Self::ISSUER.to_owned()),
+                ..Default::default()
+            },
+            ttl: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    /// Claims of a new session for `user_id`, valid for a day.
+    pub fn claims(&self, user_id: &str) -> ClerkJwt {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let issued_at = now.as_secs() as i32;
+
+        ClerkJwt {
+            azp: None,
+            exp: issued_at + self.ttl.as_secs() as i32,
+            iat: issued_at,
+            iss: Self::ISSUER.to_owned(),
+            nbf: issued_at,
+            sid: format!("sess_local_{}", now.as_nanos()),
+            sub: user_id.to_owned(),
+        }
+    }
+
+    pub fn sign(&self, claims: &ClerkJwt) -> JwtResult<String> {
+        encode(&Header::new(self.algorithm), claims, &self.encoding)
+    }
+}
+
+impl AuthProvider for LocalProvider {
+    fn kind(&self) -> AuthProviderKind {
+        AuthProviderKind::Local
+    }
+
+    fn verify<'a>(&'a self, token: &'a str) -> LocalBoxFuture<'a, Result<ClerkJwt, AuthError>> {
+        Box::pin(
+            async move { decode_claims(token, &self.decoding, self.algorithm, &self.validation) },
+        )
+    }
+}
@@ -0,0 +1,132 @@
+use actix_web::http::Method;
+
+/// Whether `ClerkMiddleware` checks the token of a request.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Access {
+    Protected,
+    Public,
+}
+
+#[derive(Clone, Debug)]
+struct RouteRule {
+    pattern: Vec<String>,
+    /// `None` matches every method.
+    methods: Option<Vec<Method>>,
+    access: Access,
+}
+
+/// Decides which requests `ClerkMiddleware` authenticates. Rules are checked in the order
+/// they were added and the first match wins, so put exceptions before the broader rules they
+/// carve out of. Requests no rule matches get the default access.
+///
+/// Patterns match whole path segments. `*` inside a segment matches any characters but `/`,
+/// a `**` segment matches any number of segments, e.g. `/api/**` or `/api/get_scenario*`.
+#[derive(Clone, Debug)]
+pub struct RouteMatcher {
+    rules: Vec<RouteRule>,
+    default: Access,
+}
+
+impl Default for RouteMatcher {
+    /// Protects every route.
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            default: Access::Protected,
+        }
+    }
+}
+
+impl RouteMatcher {
+    /// Leaves every route public unless a rule protects it.
+    pub fn public_by_default() -> Self {
+        Self {
+            rules: Vec::new(),
+            default: Access::Public,
+        }
+    }
+
+    pub fn protect(self, pattern: &str) -> Self {
+        self.rule(pattern, None, Access::Protected)
+    }
+
+    pub fn public(self, pattern: &str) -> Self {
+        self.rule(pattern, None, Access::Public)
+    }
+
+    /// Makes `pattern` public for the given methods only, e.g. browsing with `GET` while
+    /// changes still need a token.
+    pub fn public_for(self, methods: &[Method], pattern: &str) -> Self {
+        self.rule(pattern, Some(methods.to_vec()), Access::Public)
+    }
+
+    pub fn protect_for(self, methods: &[Method], pattern: &str) -> Self {
+        self.rule(pattern, Some(methods.to_vec()), Access::Protected)
+    }
+
+    fn rule(mut self, pattern: &str, methods: Option<Vec<Method>>, access: Access) -> Self {
+        self.rules.push(RouteRule {
+            pattern: segments(pattern).map(str::to_owned).collect(),
+            methods,
+            access,
+        });
+        self
+    }
+
+    pub fn access(&self, method: &Method, path: &str) -> Access {
+        let path: Vec<&str> = segments(path).collect();
+
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.methods
+                    .as_ref()
+                    .map_or(true, |methods| methods.contains(method))
+                    && matches_segments(&rule.pattern, &path)
+            })
+            .map_or(self.default, |rule| rule.access)
+    }
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+fn matches_segments(pattern: &[String], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| matches_segments(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((segment, path_rest)) => {
+                matches_glob(first, segment) && matches_segments(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Matches one segment against a pattern where `*` stands for any run of characters.
+fn matches_glob(pattern: &str, segment: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = segment.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` in the pattern.
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.len() >= last.len() && rest.ends_with(last)
+}
@@ -0,0 +1,567 @@
+use reqwest::Url;
+use serde::Deserialize;
+use std::{env, error::Error, fmt, fs, path::PathBuf, str::FromStr, time::Duration};
+
+use crate::clerk_middleware::provider::LocalProvider;
+
+/// Settings of the server, loaded once in `main` and shared with handlers as `web::Data`.
+///
+/// Each setting comes from its environment variable, else from the TOML file named by
+/// `APP_CONFIG_FILE` (an optional `config.toml` by default), else from its default. A `.env`
+/// file is read when present, deployments that inject the environment directly need none.
+#[derive(Clone)]
+pub struct AppConfig {
+    /// Origin the site is served from, e.g. `https://donationengine.g3tech.net`.
+    pub site_url: Option<String>,
+    /// Other origins allowed to call the API.
+    pub cors_allowed_origins: Vec<String>,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+}
+
+#[derive(Clone)]
+pub struct DatabaseConfig {
+    pub backend: DatabaseBackend,
+    /// Either `DATABASE_URL` or built from the `POSTGRES_*` parts, with `sslmode` and
+    /// `sslrootcert` applied.
+    pub url: String,
+    /// A plain lowercase identifier, checked when loading. Postgres only, empty for SQLite.
+    pub schema_name: String,
+    pub pool: PoolConfig,
+    /// How often startup retries an unreachable database before giving up.
+    pub connect_retries: u32,
+    /// Apply pending migrations when the server starts, otherwise run the `migrate` binary.
+    pub auto_migrate: bool,
+}
+
+impl DatabaseConfig {
+    /// The schema name as a quoted SQL identifier.
+    pub fn quoted_schema_name(&self) -> String {
+        format!("\"{}\"", self.schema_name.replace('"', "\"\""))
+    }
+}
+
+/// The database `DATABASE_URL` points at.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DatabaseBackend {
+    Postgres,
+    /// `sqlite::memory:` or a file like `sqlite://data/dev.db?mode=rwc`, for local development
+    /// and tests. Needs the `sqlite` feature.
+    Sqlite,
+}
+
+#[derive(Clone, Debug)]
+pub struct PoolConfig {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub connect_timeout: Duration,
+    pub acquire_timeout: Duration,
+    /// Idle connections above `min_connections` are closed after this long.
+    pub idle_timeout: Duration,
+    /// Connections are replaced after this long, so server side limits never cut them off.
+    pub max_lifetime: Duration,
+}
+
+#[derive(Clone)]
+pub enum AuthConfig {
+    Clerk {
+        secret_key: String,
+        issuer: Option<String>,
+        /// Defaults to the site url.
+        authorized_parties: Option<Vec<String>>,
+    },
+    Local(LocalKey),
+}
+
+/// What the local provider signs its tokens with.
+#[derive(Clone)]
+pub enum LocalKey {
+    Secret(String),
+    /// A dev RSA keypair in PEM format.
+    Rsa {
+        private_pem: Vec<u8>,
+        public_pem: Vec<u8>,
+    },
+}
+
+/// Every problem found while loading, so a misconfigured deploy is fixed in one go.
+#[derive(Debug)]
+pub struct ConfigErrors(pub Vec<String>);
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for error in &self.0 {
+            write!(f, "\n  - {error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConfigErrors {}
+
+/// Layout of the TOML file.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    site_url: Option<String>,
+    cors_allowed_origins: Option<Vec<String>>,
+    database: FileDatabase,
+    auth: FileAuth,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileDatabase {
+    user: Option<String>,
+    password: Option<String>,
+    host: Option<String>,
+    name: Option<String>,
+    schema_name: Option<String>,
+    url: Option<String>,
+    ssl_mode: Option<String>,
+    ssl_root_cert: Option<String>,
+    max_connections: Option<u32>,
+    min_connections: Option<u32>,
+    /// Timeouts in seconds.
+    connect_timeout: Option<u64>,
+    acquire_timeout: Option<u64>,
+    idle_timeout: Option<u64>,
+    max_lifetime: Option<u64>,
+    connect_retries: Option<u32>,
+    auto_migrate: Option<bool>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileAuth {
+    provider: Option<String>,
+    clerk_secret_key: Option<String>,
+    clerk_issuer: Option<String>,
+    clerk_authorized_parties: Option<Vec<String>>,
+    local_secret: Option<String>,
+    local_private_key: Option<PathBuf>,
+    local_public_key: Option<PathBuf>,
+}
+
+impl AppConfig {
+    pub fn load() -> Result<Self, ConfigErrors> {
+        let mut loader = Loader::default();
+
+        if let Err(error) = dotenvy::dotenv() {
+            if !error.not_found() {
+                loader
+                    .errors
+                    .push(format!("Unable to read .env file: {error}"));
+            }
+        }
+
+        let file = loader.file();
+        let config = loader.config(file);
+
+        if loader.errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigErrors(loader.errors))
+        }
+    }
+}
+
+#[derive(Default)]
+struct Loader {
+    errors: Vec<String>,
+}
+
+impl Loader {
+    fn file(&mut self) -> FileConfig {
+        let (path, required) = match env::var("APP_CONFIG_FILE") {
+            Ok(path) => (PathBuf::from(path), true),
+            Err(_) => (PathBuf::from("config.toml"), false),
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) if !required => return FileConfig::default(),
+            Err(error) => {
+                self.errors
+                    .push(format!("Unable to read {}: {error}", path.display()));
+                return FileConfig::default();
+            }
+        };
+
+        toml::from_str(&contents).unwrap_or_else(|error| {
+            self.errors
+                .push(format!("Unable to parse {}: {error}", path.display()));
+            FileConfig::default()
+        })
+    }
+
+    fn config(&mut self, file: FileConfig) -> AppConfig {
+        let site_url = self
+            .string("SITE_URL", file.site_url)
+            .and_then(|url| self.origin("SITE_URL", &url));
+        let cors_allowed_origins = self
+            .list("CORS_ALLOWED_ORIGINS", file.cors_allowed_origins)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|origin| self.origin("CORS_ALLOWED_ORIGINS", origin))
+            .collect();
+
+        let database = self.database(file.database);
+        let auth = self.auth(file.auth, site_url.as_deref());
+
+        AppConfig {
+            site_url,
+            cors_allowed_origins,
+            database,
+            auth,
+        }
+    }
+
+    fn database(&mut self, file: FileDatabase) -> DatabaseConfig {
+        let pool = self.pool(&file);
+        let connect_retries = self.number("DATABASE_CONNECT_RETRIES", file.connect_retries, 5);
+        let auto_migrate = self.flag("DATABASE_AUTO_MIGRATE", file.auto_migrate);
+
+        let url = self.string("DATABASE_URL", file.url);
+        if let Some(url) = url.as_deref().filter(|url| url.starts_with("sqlite:")) {
+            if !cfg!(feature = "sqlite") {
+                self.errors.push(
+                    "DATABASE_URL is a sqlite url, the server must be built with the `sqlite` feature."
+                        .to_owned(),
+                );
+            }
+
+            return DatabaseConfig {
+                backend: DatabaseBackend::Sqlite,
+                url: url.to_owned(),
+                schema_name: String::new(),
+                // One connection keeps an in-memory database alive, and SQLite has a single
+                // writer anyway.
+                pool: PoolConfig {
+                    max_connections: 1,
+                    min_connections: 1,
+                    ..pool
+                },
+                connect_retries,
+                auto_migrate,
+            };
+        }
+
+        let url = match url {
+            Some(url) => Url::parse(&url)
+                .map_err(|_| {
+                    self.errors
+                        .push("DATABASE_URL must be a valid url.".to_owned())
+                })
+                .ok(),
+            None => {
+                let user = self.required("POSTGRES_USER", file.user);
+                let password = self.required("POSTGRES_PASSWORD", file.password);
+                let host = self.required("POSTGRES_HOST", file.host);
+                let name = self.required("POSTGRES_DB", file.name);
+
+                // Set through `Url` so special characters in the password are escaped.
+                Url::parse(&format!("postgres://{host}/{name}"))
+                    .ok()
+                    .filter(|_| !host.is_empty())
+                    .and_then(|mut url| {
+                        url.set_username(&user).ok()?;
+                        url.set_password(Some(&password)).ok()?;
+                        Some(url)
+                    })
+                    .or_else(|| {
+                        if !host.is_empty() {
+                            self.errors
+                                .push(format!("POSTGRES_HOST must be a host name, got `{host}`."));
+                        }
+                        None
+                    })
+            }
+        };
+
+        let ssl_mode = self.string("DATABASE_SSL_MODE", file.ssl_mode);
+        if let Some(ssl_mode) = &ssl_mode {
+            const SSL_MODES: [&str; 6] = [
+                "disable",
+                "allow",
+                "prefer",
+                "require",
+                "verify-ca",
+                "verify-full",
+            ];
+            if !SSL_MODES.contains(&ssl_mode.as_str()) {
+                self.errors.push(format!(
+                    "DATABASE_SSL_MODE must be one of {}, got `{ssl_mode}`.",
+                    SSL_MODES.join(", ")
+                ));
+            }
+        }
+        let ssl_root_cert = self.string("DATABASE_SSL_ROOT_CERT", file.ssl_root_cert);
+        if let Some(ssl_root_cert) = &ssl_root_cert {
+            if fs::metadata(ssl_root_cert).is_err() {
+                self.errors.push(format!(
+                    "DATABASE_SSL_ROOT_CERT: unable to read `{ssl_root_cert}`."
+                ));
+            }
+        }
+
+        let url = url.map(|mut url| {
+            if !matches!(url.scheme(), "postgres" | "postgresql") {
+                self.errors.push(format!(
+                    "DATABASE_URL must be a postgres:// or sqlite: url, got `{}://`.",
+                    url.scheme()
+                ));
+            }
+            with_query(&mut url, "sslmode", ssl_mode.as_deref());
+            with_query(&mut url, "sslrootcert", ssl_root_cert.as_deref());
+            url
+        });
+
+        let schema_name = self.required("POSTGRES_SCHEMA_NAME", file.schema_name);
+        if !schema_name.is_empty() && !is_identifier(&schema_name) {
+            self.errors.push(format!(
+                "POSTGRES_SCHEMA_NAME must be lowercase letters, digits and underscores, not starting with a digit and at most 63 long, got `{schema_name}`."
+            ));
+        }
+
+        DatabaseConfig {
+            backend: DatabaseBackend::Postgres,
+            url: url.map(String::from).unwrap_or_default(),
+            schema_name,
+            pool,
+            connect_retries,
+            auto_migrate,
+        }
+    }
+
+    fn pool(&mut self, file: &FileDatabase) -> PoolConfig {
+        let max_connections = self.number("DATABASE_MAX_CONNECTIONS", file.max_connections, 20);
+        let min_connections = self.number("DATABASE_MIN_CONNECTIONS", file.min_connections, 2);
+
+        if max_connections == 0 {
+            self.errors
+                .push("DATABASE_MAX_CONNECTIONS must be at least 1.".to_owned());
+        }
+        if min_connections > max_connections {
+            self.errors.push(format!(
+                "DATABASE_MIN_CONNECTIONS ({min_connections}) must not exceed DATABASE_MAX_CONNECTIONS ({max_connections})."
+            ));
+        }
+
+        PoolConfig {
+            max_connections,
+            min_connections,
+            connect_timeout: self.seconds("DATABASE_CONNECT_TIMEOUT", file.connect_timeout, 8),
+            acquire_timeout: self.seconds("DATABASE_ACQUIRE_TIMEOUT", file.acquire_timeout, 8),
+            idle_timeout: self.seconds("DATABASE_IDLE_TIMEOUT", file.idle_timeout, 10 * 60),
+            max_lifetime: self.seconds("DATABASE_MAX_LIFETIME", file.max_lifetime, 30 * 60),
+        }
+    }
+
+    fn auth(&mut self, file: FileAuth, site_url: Option<&str>) -> AuthConfig {
+        let provider = self.string("AUTH_PROVIDER", file.provider);
+
+        match provider.as_deref().unwrap_or("clerk") {
+            "local" => AuthConfig::Local(self.local_key(
+                file.local_secret,
+                file.local_private_key,
+                file.local_public_key,
+            )),
+            provider => {
+                if provider != "clerk" {
+                    self.errors.push(format!(
+                        "AUTH_PROVIDER must be `clerk` or `local`, got `{provider}`."
+                    ));
+                }
+
+                let secret_key = self.required("CLERK_SECRET_KEY", file.clerk_secret_key);
+                if !secret_key.is_empty() && !secret_key.starts_with("sk_") {
+                    self.errors.push(
+                        "CLERK_SECRET_KEY must be a Clerk secret key starting with `sk_`."
+                            .to_owned(),
+                    );
+                }
+
+                let issuer = self.string("CLERK_ISSUER", file.clerk_issuer);
+                if let Some(issuer) = &issuer {
+                    self.origin("CLERK_ISSUER", issuer);
+                }
+
+                let authorized_parties = self
+                    .list("CLERK_AUTHORIZED_PARTIES", file.clerk_authorized_parties)
+                    .or_else(|| site_url.map(|url| vec![url.to_owned()]));
+
+                AuthConfig::Clerk {
+                    secret_key,
+                    issuer,
+                    authorized_parties,
+                }
+            }
+        }
+    }
+
+    fn local_key(
+        &mut self,
+        secret: Option<String>,
+        private_key: Option<PathBuf>,
+        public_key: Option<PathBuf>,
+    ) -> LocalKey {
+        let private_key = self
+            .string("AUTH_LOCAL_PRIVATE_KEY", None)
+            .map(PathBuf::from)
+            .or(private_key);
+        let public_key = self
+            .string("AUTH_LOCAL_PUBLIC_KEY", None)
+            .map(PathBuf::from)
+            .or(public_key);
+
+        match (private_key, public_key) {
+            (Some(private_key), Some(public_key)) => {
+                let key = LocalKey::Rsa {
+                    private_pem: self.read("AUTH_LOCAL_PRIVATE_KEY", private_key),
+                    public_pem: self.read("AUTH_LOCAL_PUBLIC_KEY", public_key),
+                };
+                if LocalProvider::from_key(&key).is_err() {
+                    self.errors.push(
+                        "AUTH_LOCAL_PRIVATE_KEY and AUTH_LOCAL_PUBLIC_KEY must be an RSA keypair in PEM format."
+                            .to_owned(),
+                    );
+                }
+                key
+            }
+            (None, None) => {
+                let secret = self.required("AUTH_LOCAL_SECRET", secret);
+                if !secret.is_empty() && secret.len() < 32 {
+                    self.errors
+                        .push("AUTH_LOCAL_SECRET must be at least 32 characters.".to_owned());
+                }
+                LocalKey::Secret(secret)
+            }
+            _ => {
+                self.errors.push(
+                    "AUTH_LOCAL_PRIVATE_KEY and AUTH_LOCAL_PUBLIC_KEY must be set together."
+                        .to_owned(),
+                );
+                LocalKey::Secret(String::new())
+            }
+        }
+    }
+
+    /// The environment variable `name` when set, else the file's value.
+    fn string(&self, name: &str, file: Option<String>) -> Option<String> {
+        env::var(name)
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .or(file)
+    }
+
+    fn required(&mut self, name: &str, file: Option<String>) -> String {
+        self.string(name, file).unwrap_or_else(|| {
+            self.errors.push(format!("{name} is not set."));
+            String::new()
+        })
+    }
+
+    fn number<T: FromStr>(&mut self, name: &str, file: Option<T>, default: T) -> T {
+        match env::var(name) {
+            Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+                self.errors
+                    .push(format!("{name} must be a whole number, got `{value}`."));
+                default
+            }),
+            Err(_) => file.unwrap_or(default),
+        }
+    }
+
+    fn flag(&mut self, name: &str, file: Option<bool>) -> bool {
+        match env::var(name).map(|value| value.trim().to_lowercase()) {
+            Ok(value) => match value.as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" | "" => false,
+                _ => {
+                    self.errors
+                        .push(format!("{name} must be true or false, got `{value}`."));
+                    false
+                }
+            },
+            Err(_) => file.unwrap_or_default(),
+        }
+    }
+
+    fn seconds(&mut self, name: &str, file: Option<u64>, default: u64) -> Duration {
+        let seconds = self.number(name, file, default);
+        if seconds == 0 {
+            self.errors
+                .push(format!("{name} must be at least 1 second."));
+        }
+        Duration::from_secs(seconds)
+    }
+
+    /// A comma separated list from the environment, else the file's list.
+    fn list(&self, name: &str, file: Option<Vec<String>>) -> Option<Vec<String>> {
+        let list = self.string(name, None).map(|list| {
+            list.split(',')
+                .map(|item| item.trim().to_owned())
+                .filter(|item| !item.is_empty())
+                .collect()
+        });
+
+        list.or(file)
+    }
+
+    /// The origin of an http(s) url, without a trailing slash or path.
+    fn origin(&mut self, name: &str, url: &str) -> Option<String> {
+        match Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {
+                Some(parsed.origin().ascii_serialization())
+            }
+            _ => {
+                self.errors
+                    .push(format!("{name} must be an http(s) url, got `{url}`."));
+                None
+            }
+        }
+    }
+
+    fn read(&mut self, name: &str, path: PathBuf) -> Vec<u8> {
+        fs::read(&path).unwrap_or_else(|error| {
+            self.errors.push(format!(
+                "{name}: unable to read {}: {error}",
+                path.display()
+            ));
+            Vec::new()
+        })
+    }
+}
+
+/// Sets the query parameter `key` of `url` to `value`, replacing one the url already has.
+fn with_query(url: &mut Url, key: &str, value: Option<&str>) {
+    let Some(value) = value else {
+        return;
+    };
+
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| name != key)
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(key, value);
+}
+
+/// Whether `name` can be used as a Postgres identifier without surprises: lowercase, since
+/// unquoted names are folded to it, and no longer than Postgres keeps.
+fn is_identifier(name: &str) -> bool {
+    name.len() <= 63
+        && name
+            .chars()
+            .next()
+            .is_some_and(|first| first.is_ascii_lowercase() || first == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
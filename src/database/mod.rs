@@ -1,69 +1,169 @@
-use crate::migration::Migrator;
+pub mod nonprofits;
+pub mod seed;
 
 use sea_orm::{
-    ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DbBackend, Statement,
+    ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DatabaseTransaction, DbBackend,
+    DbErr, Statement, TransactionTrait,
 };
-use sea_orm_migration::MigratorTrait;
-use std::{env, time::Duration};
-
-pub async fn init_database() -> DatabaseConnection {
-    dotenvy::dotenv().expect("Unable to read .env file.");
-
-    let postgres_user = env::var("POSTGRES_USER").expect("Unable to load POSTGRES_USER from env.");
-    let postgres_password =
-        env::var("POSTGRES_PASSWORD").expect("Unable to load POSTGRES_PASSWORD from env.");
-    let postgres_db = env::var("POSTGRES_DB").expect("Unable to load POSTGRES_DB from env.");
-    let postgres_schema_name =
-        env::var("POSTGRES_SCHEMA_NAME").expect("Unable to load POSTGRES_SCHEMA_NAME from env.");
-    let postgres_host = env::var("POSTGRES_HOST").expect("Unable to load POSTGRES_HOST from env.");
-
-    let mut opt = ConnectOptions::new(format!(
-        "postgres://{postgres_user}:{postgres_password}@{postgres_host}/{postgres_db}"
-    ));
-    opt.max_connections(100)
-        .min_connections(5)
-        .connect_timeout(Duration::from_secs(8))
-        .acquire_timeout(Duration::from_secs(8))
-        .idle_timeout(Duration::from_secs(8))
-        .max_lifetime(Duration::from_secs(8))
+use sea_orm_migration::{MigrationStatus, MigratorTrait};
+use std::time::Duration;
+
+use crate::{
+    config::{DatabaseBackend, DatabaseConfig},
+    migration::Migrator,
+};
+
+/// Key of the advisory lock held while migrating, the same for every instance of the server.
+const MIGRATION_LOCK: i64 = 0x646f_6e61_7469_6f6e;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MigrateCommand {
+    /// Applies pending migrations, all of them when `None`.
+    Up(Option<u32>),
+    /// Rolls back applied migrations, all of them when `None`.
+    Down(Option<u32>),
+    /// Drops every table and applies all migrations again.
+    Fresh,
+}
+
+/// Connects and, when `auto_migrate` is set, applies pending migrations.
+pub async fn init_database(config: &DatabaseConfig) -> DatabaseConnection {
+    let db = connect_database(config).await;
+
+    if config.auto_migrate {
+        migrate(&db, config, MigrateCommand::Up(None))
+            .await
+            .expect("Unable to run migrations.");
+    }
+
+    db
+}
+
+pub async fn connect_database(config: &DatabaseConfig) -> DatabaseConnection {
+    let pool = &config.pool;
+
+    let mut opt = ConnectOptions::new(config.url.clone());
+    opt.max_connections(pool.max_connections)
+        .min_connections(pool.min_connections)
+        .connect_timeout(pool.connect_timeout)
+        .acquire_timeout(pool.acquire_timeout)
         .sqlx_logging(true)
-        .sqlx_logging_level(log::LevelFilter::Info)
-        .set_schema_search_path(postgres_schema_name.clone());
+        .sqlx_logging_level(log::LevelFilter::Info);
 
-    let db = Database::connect(opt.clone())
-        .await
-        .expect("Unable to make connection to db.");
-
-    // Postgres does not allow prepared statements for these statements.
-    let create_schema = Statement::from_string(
-        DbBackend::Postgres,
-        format!(r#"CREATE SCHEMA IF NOT EXISTS "{postgres_schema_name}""#),
-    );
-    db.execute(create_schema)
-        .await
-        .expect("Unable to create schema.");
-
-    let grant_schema_permissions = Statement::from_string(
-        DbBackend::Postgres,
-        format!(
-            r#"GRANT ALL ON ALL TABLES IN SCHEMA "{postgres_schema_name}" TO "{postgres_user}""#
-        ),
-    );
-    db.execute(grant_schema_permissions)
-        .await
-        .expect("Unable to grant schema permissions.");
+    match config.backend {
+        DatabaseBackend::Postgres => {
+            opt.idle_timeout(pool.idle_timeout)
+                .max_lifetime(pool.max_lifetime)
+                // Interpolated into `SET search_path` by sea-orm, safe as the config only
+                // accepts plain identifiers.
+                .set_schema_search_path(config.schema_name.clone());
+        }
+        // The one connection is never closed, an in-memory database would be lost with it.
+        DatabaseBackend::Sqlite => {}
+    }
 
-    let set_search_path = Statement::from_string(
-        DbBackend::Postgres,
-        format!(r#"ALTER ROLE "{postgres_user}" SET search_path TO "{postgres_schema_name}""#),
-    );
-    db.execute(set_search_path)
+    connect(opt, config.connect_retries)
         .await
-        .expect("Unable to set search path.");
+        .expect("Unable to make connection to db.")
+}
 
-    Migrator::up(&db, None)
-        .await
-        .expect("Unable to run migrations.");
+/// Runs `command` in one transaction holding an advisory lock, so servers starting together
+/// wait for each other and every migration is applied once. Creates the schema if needed, and
+/// seeds the curated rows once no migration is pending.
+pub async fn migrate(
+    db: &DatabaseConnection,
+    config: &DatabaseConfig,
+    command: MigrateCommand,
+) -> Result<(), DbErr> {
+    let txn = begin_migration(db, config).await?;
 
-    db
+    match command {
+        MigrateCommand::Up(steps) => Migrator::up(&txn, steps).await?,
+        MigrateCommand::Down(steps) => Migrator::down(&txn, steps).await?,
+        MigrateCommand::Fresh => Migrator::fresh(&txn).await?,
+    }
+
+    if Migrator::get_pending_migrations(&txn).await?.is_empty() {
+        seed::seed(&txn).await?;
+    }
+
+    txn.commit().await
+}
+
+/// Name and status of every migration, oldest first.
+pub async fn migration_status(
+    db: &DatabaseConnection,
+    config: &DatabaseConfig,
+) -> Result<Vec<(String, MigrationStatus)>, DbErr> {
+    // Listing creates the migrations table, so it needs the schema and the lock as well.
+    let txn = begin_migration(db, config).await?;
+
+    let migrations = Migrator::get_migration_with_status(&txn)
+        .await?
+        .iter()
+        .map(|migration| (migration.name().to_owned(), migration.status()))
+        .collect();
+
+    txn.commit().await?;
+    Ok(migrations)
+}
+
+async fn begin_migration(
+    db: &DatabaseConnection,
+    config: &DatabaseConfig,
+) -> Result<DatabaseTransaction, DbErr> {
+    let txn = db.begin().await?;
+
+    match config.backend {
+        DatabaseBackend::Postgres => {
+            txn.execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "SELECT pg_advisory_xact_lock($1)",
+                [MIGRATION_LOCK.into()],
+            ))
+            .await?;
+
+            // Identifiers cannot be bound as parameters, the name is validated and quoted
+            // instead.
+            txn.execute(Statement::from_string(
+                DbBackend::Postgres,
+                format!(
+                    "CREATE SCHEMA IF NOT EXISTS {}",
+                    config.quoted_schema_name()
+                ),
+            ))
+            .await?;
+        }
+        // SQLite has no schemas, and is only meant for a single local server.
+        DatabaseBackend::Sqlite => {}
+    }
+
+    Ok(txn)
+}
+
+/// Connects and checks the connection works, retrying with exponential backoff so the server
+/// can start alongside a database that is still booting.
+async fn connect(opt: ConnectOptions, retries: u32) -> Result<DatabaseConnection, DbErr> {
+    let mut backoff = Duration::from_millis(500);
+    let mut attempt = 0;
+
+    loop {
+        let result = match Database::connect(opt.clone()).await {
+            Ok(db) => db.ping().await.map(|_| db),
+            Err(error) => Err(error),
+        };
+
+        match result {
+            Ok(db) => return Ok(db),
+            Err(error) if attempt < retries => {
+                attempt += 1;
+                log::warn!(
+                    "Unable to connect to db, retry {attempt}/{retries} in {backoff:?}: {error}"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(Duration::from_secs(30));
+            }
+            Err(error) => return Err(error),
+        }
+    }
 }
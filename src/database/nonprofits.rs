@@ -0,0 +1,106 @@
+//! Loads the IRS Publication 78 data file into the `nonprofit` table, which the tax year
+//! report reads deductibility from. The file is downloaded from
+//! <https://www.irs.gov/charities-non-profits/tax-exempt-organization-search-bulk-data-downloads>
+//! and is one pipe separated line per organization:
+//! `EIN|Legal Name|City|State|Country|Deductibility Codes`.
+
+use std::io::Read;
+
+use sea_orm::{sea_query::OnConflict, ConnectionTrait, DbErr, EntityTrait, Set};
+
+use crate::entity::nonprofit;
+
+/// Rows per insert, well under the database's limit on bound parameters.
+const BATCH_SIZE: usize = 300;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoadSummary {
+    pub loaded: usize,
+    /// Line numbers that have no valid EIN or name.
+    pub skipped: Vec<u64>,
+}
+
+/// Upserts every organization in `data` on its EIN, so loading a newer file updates names and
+/// codes. Organizations missing from a newer file are left in place.
+pub async fn load_publication_78<C: ConnectionTrait, R: Read>(
+    db: &C,
+    data: R,
+) -> Result<LoadSummary, DbErr> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b'|')
+        .has_headers(false)
+        .flexible(true)
+        .quoting(false)
+        .trim(csv::Trim::All)
+        .from_reader(data);
+
+    let mut summary = LoadSummary::default();
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+    for record in reader.records() {
+        let record = record.map_err(|error| DbErr::Custom(error.to_string()))?;
+        // The file starts with blank lines.
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+
+        let ein = record
+            .get(0)
+            .and_then(|ein| ein.parse::<i32>().ok())
+            .filter(|ein| (1..=999_999_999).contains(ein));
+        let name = record.get(1).filter(|name| !name.is_empty());
+        let (Some(ein), Some(name)) = (ein, name) else {
+            summary
+                .skipped
+                .push(record.position().map_or(0, |position| position.line()));
+            continue;
+        };
+
+        batch.push(nonprofit::ActiveModel {
+            ein: Set(ein),
+            name: Set(name.to_owned()),
+            deductibility_code: Set(record
+                .get(5)
+                .filter(|code| !code.is_empty())
+                .map(str::to_owned)),
+        });
+
+        if batch.len() == BATCH_SIZE {
+            summary.loaded += upsert(db, std::mem::take(&mut batch)).await?;
+        }
+    }
+    summary.loaded += upsert(db, batch).await?;
+
+    Ok(summary)
+}
+
+async fn upsert<C: ConnectionTrait>(
+    db: &C,
+    mut batch: Vec<nonprofit::ActiveModel>,
+) -> Result<usize, DbErr> {
+    // A file listing an EIN twice would make one statement update the same row twice, which
+    // Postgres refuses. The later line wins.
+    batch.reverse();
+    let mut eins = std::collections::HashSet::new();
+    batch.retain(|organization| eins.insert(organization.ein.clone().unwrap()));
+    batch.reverse();
+
+    let count = batch.len();
+    if count == 0 {
+        return Ok(0);
+    }
+
+    nonprofit::Entity::insert_many(batch)
+        .on_conflict(
+            OnConflict::column(nonprofit::Column::Ein)
+                .update_columns([
+                    nonprofit::Column::Name,
+                    nonprofit::Column::DeductibilityCode,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+
+    Ok(count)
+}
@@ -0,0 +1,74 @@
+//! Curated rows the app ships with. Each row has a key that never changes once released, so
+//! seeding is an upsert on the key: running it again changes nothing, and a later release can
+//! rename a seed or change its value by editing the lists below. New keys are appended and get
+//! ids in the order they are listed. Rows dropped from a list are left in the database.
+
+use sea_orm::{sea_query::OnConflict, ConnectionTrait, DbErr, EntityTrait, Set};
+
+use crate::entity::{color, default_category};
+
+/// Key and name.
+pub const DEFAULT_CATEGORIES: [(&str, &str); 10] = [
+    ("animal_welfare", "Animal Welfare"),
+    ("culture", "Culture"),
+    ("education", "Education"),
+    ("environment", "Environment"),
+    ("global_health", "Global Health"),
+    ("health_care", "Health Care"),
+    ("humanitarian_aid", "Humanitarian Aid"),
+    ("justice", "Justice"),
+    ("local_causes", "Local Causes"),
+    ("scientific_research", "Scientific Research"),
+];
+
+/// Key, name and hex value.
+pub const COLORS: [(&str, &str, &str); 10] = [
+    ("red", "Red", "#FF0000"),
+    ("orange", "Orange", "#FF8000"),
+    ("brown", "Brown", "#604525"),
+    ("yellow", "Yellow", "#FFFF00"),
+    ("lime_green", "Lime Green", "#00FF00"),
+    ("cyan", "Cyan", "#00FF80"),
+    ("aqua", "Aqua", "#00FFFF"),
+    ("light_blue", "Light Blue", "#0080FF"),
+    ("blue", "Blue", "#0000FF"),
+    ("pink", "Pink", "#FF007F"),
+];
+
+/// Inserts missing seeds and updates the rest to match the lists. Needs every migration
+/// applied, `database::migrate` calls it after migrating up.
+pub async fn seed<C: ConnectionTrait>(db: &C) -> Result<(), DbErr> {
+    // One row at a time, a single statement would not promise ids in list order.
+    for (key, name) in DEFAULT_CATEGORIES {
+        default_category::Entity::insert(default_category::ActiveModel {
+            key: Set(key.to_owned()),
+            name: Set(name.to_owned()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::column(default_category::Column::Key)
+                .update_column(default_category::Column::Name)
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    }
+
+    for (key, name, value) in COLORS {
+        color::Entity::insert(color::ActiveModel {
+            key: Set(key.to_owned()),
+            name: Set(name.to_owned()),
+            value: Set(value.to_owned()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::column(color::Column::Key)
+                .update_columns([color::Column::Name, color::Column::Value])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    }
+
+    Ok(())
+}
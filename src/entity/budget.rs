@@ -1,17 +1,42 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, DeriveEntityModel, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+pub enum BudgetMode {
+    #[sea_orm(string_value = "percent")]
+    Percent,
+    #[sea_orm(string_value = "brackets")]
+    Brackets,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+pub enum IncomeBasis {
+    #[sea_orm(string_value = "gross")]
+    Gross,
+    #[sea_orm(string_value = "net")]
+    Net,
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
 #[sea_orm(table_name = "budget")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
+    #[sea_orm(unique)]
     pub list_id: i32,
 
     #[sea_orm(default_value = "Decimal::new(50_000, 0)")]
     pub total_value: Decimal,
     pub donation_percent: Decimal,
     pub value_override: Option<Decimal>,
+
+    pub mode: BudgetMode,
+    pub income_basis: IncomeBasis,
+    pub net_value: Option<Decimal>,
+
+    pub version: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -22,6 +47,8 @@ pub enum Relation {
         to = "super::list::Column::Id"
     )]
     List,
+    #[sea_orm(has_many = "super::giving_bracket::Entity")]
+    GivingBracket,
 }
 
 impl Related<super::list::Entity> for Entity {
@@ -30,4 +57,10 @@ impl Related<super::list::Entity> for Entity {
     }
 }
 
+impl Related<super::giving_bracket::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GivingBracket.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -6,6 +6,9 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
+    /// Stable identity of the seeded row, see `database::seed`.
+    #[sea_orm(unique)]
+    pub key: String,
     pub name: String,
     pub value: String,
 }
@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "donation")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub list_id: i32,
+
+    pub ein: i32,
+
+    pub amount: Decimal,
+    pub donated_on: Date,
+    pub receipt_reference: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::list::Entity",
+        from = "Column::ListId",
+        to = "super::list::Column::Id"
+    )]
+    List,
+}
+
+impl Related<super::list::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::List.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
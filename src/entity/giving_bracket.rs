@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "giving_bracket")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub budget_id: i32,
+
+    pub threshold: Decimal,
+    pub percent: Decimal,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::budget::Entity",
+        from = "Column::BudgetId",
+        to = "super::budget::Column::Id"
+    )]
+    Budget,
+}
+
+impl Related<super::budget::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Budget.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,12 +1,19 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, DeriveEntityModel, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
 #[sea_orm(table_name = "list")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub user_id: String,
+    /// The live list this list is a what-if scenario of, `None` for the live list.
+    pub scenario_of: Option<i32>,
+    pub name: Option<String>,
+
+    /// Incremented by every save, used to detect concurrent edits. Budget, category and entry
+    /// rows store the list version of the save that last changed them.
+    pub version: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -15,6 +22,10 @@ pub enum Relation {
     Budget,
     #[sea_orm(has_many = "super::category::Entity")]
     Category,
+    #[sea_orm(has_many = "super::donation::Entity")]
+    Donation,
+    #[sea_orm(has_many = "super::list_event::Entity")]
+    ListEvent,
 }
 
 impl Related<super::budget::Entity> for Entity {
@@ -29,4 +40,16 @@ impl Related<super::category::Entity> for Entity {
     }
 }
 
+impl Related<super::donation::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Donation.def()
+    }
+}
+
+impl Related<super::list_event::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ListEvent.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
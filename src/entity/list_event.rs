@@ -0,0 +1,93 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+pub enum EventEntity {
+    #[sea_orm(string_value = "budget")]
+    Budget,
+    #[sea_orm(string_value = "giving_bracket")]
+    GivingBracket,
+    #[sea_orm(string_value = "category")]
+    Category,
+    #[sea_orm(string_value = "entry")]
+    Entry,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+pub enum EventAction {
+    #[sea_orm(string_value = "insert")]
+    Insert,
+    #[sea_orm(string_value = "update")]
+    Update,
+    #[sea_orm(string_value = "delete")]
+    Delete,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+pub enum EventState {
+    #[sea_orm(string_value = "applied")]
+    Applied,
+    #[sea_orm(string_value = "undone")]
+    Undone,
+    /// Undone and then replaced by a newer change, so it can no longer be redone.
+    #[sea_orm(string_value = "discarded")]
+    Discarded,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+pub enum EventOrigin {
+    #[sea_orm(string_value = "save")]
+    Save,
+    #[sea_orm(string_value = "undo")]
+    Undo,
+    #[sea_orm(string_value = "redo")]
+    Redo,
+}
+
+#[derive(Clone, Debug, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "list_event")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub list_id: i32,
+
+    /// Events written by the same save share a change set and are undone together.
+    pub change_set: i32,
+    pub actor: String,
+    pub created_at: DateTimeUtc,
+
+    pub entity: EventEntity,
+    pub entity_id: i32,
+    pub action: EventAction,
+    /// The row before and after the change, `None` for inserts and deletes respectively.
+    pub before: Option<Json>,
+    pub after: Option<Json>,
+
+    pub state: EventState,
+    /// Undo and redo events only move rows back and forth, they are never undone themselves.
+    pub origin: EventOrigin,
+    /// The save change set an undo or redo event reverted or replayed.
+    pub origin_change_set: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::list::Entity",
+        from = "Column::ListId",
+        to = "super::list::Column::Id"
+    )]
+    List,
+}
+
+impl Related<super::list::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::List.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -2,5 +2,9 @@ pub mod budget;
 pub mod category;
 pub mod color;
 pub mod default_category;
+pub mod donation;
 pub mod entry;
+pub mod giving_bracket;
 pub mod list;
+pub mod list_event;
+pub mod nonprofit;
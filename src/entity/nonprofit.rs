@@ -0,0 +1,18 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "nonprofit")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub ein: i32,
+    pub name: String,
+
+    /// IRS Publication 78 deductibility code, `None` when the organization is not eligible.
+    pub deductibility_code: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
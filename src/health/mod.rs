@@ -0,0 +1,57 @@
+use actix_web::{get, http::StatusCode, web::Data, HttpResponse};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+
+use crate::config::AppConfig;
+
+#[derive(Serialize)]
+pub struct Health {
+    /// `ok`, or `unavailable` when the database cannot be reached.
+    pub status: &'static str,
+    pub database: PoolStats,
+}
+
+#[derive(Serialize)]
+pub struct PoolStats {
+    pub reachable: bool,
+    /// Open connections, idle or in use.
+    pub connections: u32,
+    pub idle: usize,
+    pub max_connections: u32,
+}
+
+/// Liveness of the server and its database pool, for load balancers and uptime checks. Public,
+/// it reports no data of any user.
+#[get("/healthz")]
+pub async fn healthz(db: Data<DatabaseConnection>, config: Data<AppConfig>) -> HttpResponse {
+    let reachable = db.ping().await.is_ok();
+
+    let (connections, idle) = match db.get_ref() {
+        DatabaseConnection::SqlxPostgresPoolConnection(_) => {
+            let pool = db.get_postgres_connection_pool();
+            (pool.size(), pool.num_idle())
+        }
+        #[cfg(feature = "sqlite")]
+        DatabaseConnection::SqlxSqlitePoolConnection(_) => {
+            let pool = db.get_sqlite_connection_pool();
+            (pool.size(), pool.num_idle())
+        }
+        _ => (0, 0),
+    };
+
+    let (status, code) = if reachable {
+        ("ok", StatusCode::OK)
+    } else {
+        ("unavailable", StatusCode::SERVICE_UNAVAILABLE)
+    };
+
+    HttpResponse::build(code).json(Health {
+        status,
+        database: PoolStats {
+            reachable,
+            connections,
+            idle,
+            max_connections: config.database.pool.max_connections,
+        },
+    })
+}
@@ -1,9 +1,17 @@
 pub mod app;
 pub mod list;
+pub mod live;
+pub mod tax;
 use cfg_if::cfg_if;
 
 cfg_if! { if #[cfg(feature = "ssr")] {
+  pub mod clerk_middleware;
+  pub mod config;
+  pub mod database;
   pub mod entity;
+  pub mod health;
+  pub mod migration;
+  pub mod report;
 }}
 
 cfg_if! {
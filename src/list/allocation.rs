@@ -0,0 +1,432 @@
+use std::collections::BTreeMap;
+use std::ops::ControlFlow;
+
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{Budget, BudgetMode, Category, Entry, GivingBracket, List};
+
+/// Everything `List::process_list` computes for a list. Never stored or sent to the server,
+/// it is recomputed from the list whenever it is needed.
+///
+/// Categories are keyed by `row_key` and entries by `row_key` within their category.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Allocation {
+    pub budget: BudgetAllocation,
+    pub categories: BTreeMap<i32, CategoryAllocation>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct BudgetAllocation {
+    pub donation_value: Decimal,
+    /// Empty unless the budget is in `BudgetMode::Brackets`.
+    pub bracket_breakdown: Vec<BracketAllocation>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct BracketAllocation {
+    pub threshold: Decimal,
+    pub ceiling: Option<Decimal>,
+    pub percent: Decimal,
+
+    pub income_in_bracket: Decimal,
+    pub donation_value: Decimal,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct CategoryAllocation {
+    /// Enabled and has entries. Categories that are not included, and their entries, are zero.
+    pub included: bool,
+    pub has_entry_overrides: bool,
+
+    pub donation_value: Decimal,
+    pub donation_percent: Decimal,
+
+    pub entries: BTreeMap<i32, EntryAllocation>,
+}
+
+impl CategoryAllocation {
+    /// The allocation of `entry`, found at `position` in its category.
+    pub fn entry(&self, position: usize, entry: &Entry) -> Option<&EntryAllocation> {
+        self.entries.get(&row_key(entry.id, position))
+    }
+}
+
+/// `donation_percent` is the entry's share of its category.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct EntryAllocation {
+    pub donation_value: Decimal,
+    pub donation_percent: Decimal,
+}
+
+/// The key of a row in an `Allocation`. Saved rows are keyed by their id. Rows that have not
+/// been saved yet all have id 0, they get a temporary negative key from their position
+/// instead, -1 for the first row.
+pub fn row_key(id: i32, position: usize) -> i32 {
+    if id != 0 {
+        id
+    } else {
+        -(position as i32) - 1
+    }
+}
+
+impl Allocation {
+    /// The allocation of `category`, found at `position` in the processed list.
+    pub fn category(&self, position: usize, category: &Category) -> Option<&CategoryAllocation> {
+        self.categories.get(&row_key(category.id, position))
+    }
+
+    /// Total donation across the included categories.
+    pub fn allocated_value(&self) -> Decimal {
+        self.categories
+            .values()
+            .filter(|category| category.included)
+            .map(|category| category.donation_value)
+            .sum()
+    }
+}
+
+impl Budget {
+    fn bracket_breakdown(&self) -> Vec<BracketAllocation> {
+        let income = self.income();
+
+        let mut brackets: Vec<&GivingBracket> = self.brackets.iter().collect();
+        brackets.sort_by_key(|bracket| bracket.threshold);
+
+        brackets
+            .iter()
+            .enumerate()
+            .map(|(index, bracket)| {
+                let ceiling = brackets.get(index + 1).map(|next| next.threshold);
+                let income_in_bracket = (ceiling.map_or(income, |ceiling| ceiling.min(income))
+                    - bracket.threshold)
+                    .max(Decimal::ZERO);
+
+                BracketAllocation {
+                    threshold: bracket.threshold,
+                    ceiling,
+                    percent: bracket.percent,
+                    income_in_bracket,
+                    donation_value: (income_in_bracket * bracket.percent).round_dp(2),
+                }
+            })
+            .collect()
+    }
+}
+
+impl List {
+    pub fn is_list_valid(&self) -> bool {
+        if self.categories.is_empty() {
+            return true;
+        }
+
+        let allocation = self.process_list();
+        let mut categories_value_sum = Decimal::default();
+        let mut categories_percent_sum = Decimal::default();
+        let mut entry_count = 0;
+
+        let checked = self
+            .categories
+            .iter()
+            .enumerate()
+            .filter_map(|(position, category)| {
+                allocation
+                    .category(position, category)
+                    .map(|allocated| (category, allocated))
+            })
+            .filter(|(_, allocated)| allocated.included)
+            .filter(|(category, _)| !category.entries.is_empty())
+            .try_for_each(|(category, allocated)| {
+                if allocated.donation_value == Decimal::ZERO
+                    || allocated.donation_percent == Decimal::ZERO
+                {
+                    return ControlFlow::Break(());
+                }
+
+                categories_value_sum += allocated.donation_value;
+                categories_percent_sum += allocated.donation_percent;
+                entry_count += category.entries.len();
+
+                let mut entries_value_sum = Decimal::default();
+                let mut entries_percent_sum = Decimal::default();
+
+                category
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| entry.enabled)
+                    .filter_map(|(position, entry)| allocated.entry(position, entry))
+                    .try_for_each(|entry| {
+                        if entry.donation_value == Decimal::ZERO
+                            || entry.donation_percent == Decimal::ZERO
+                        {
+                            return ControlFlow::Break(());
+                        }
+
+                        entries_value_sum += entry.donation_value;
+                        entries_percent_sum += entry.donation_percent;
+                        ControlFlow::Continue(())
+                    })?;
+
+                let entries_value_overflow =
+                    (entries_value_sum - allocated.donation_value).abs() > Decimal::ONE;
+                let entries_percent_overflow =
+                    (entries_percent_sum - Decimal::ONE).abs() > Decimal::new(1, 2);
+
+                if entries_value_overflow || entries_percent_overflow {
+                    return ControlFlow::Break(());
+                }
+
+                ControlFlow::Continue(())
+            });
+
+        if checked.is_break() {
+            return false;
+        }
+        if entry_count == 0 {
+            return true;
+        }
+
+        let categories_value_overflow =
+            (categories_value_sum - allocation.budget.donation_value).abs() > Decimal::ONE;
+        let categories_percent_overflow =
+            (categories_percent_sum - Decimal::ONE).abs() > Decimal::new(1, 2);
+
+        !(categories_value_overflow || categories_percent_overflow)
+    }
+
+    /// Computes the donation of the budget, every category and every entry from scratch. The
+    /// list is left untouched, so the result only depends on its current fields.
+    pub fn process_list(&self) -> Allocation {
+        let budget = self.process_budget();
+        let categories = self.process_categories(budget.donation_value);
+
+        Allocation {
+            budget,
+            categories: self
+                .categories
+                .iter()
+                .zip(categories)
+                .enumerate()
+                .map(|(position, (category, allocated))| {
+                    (row_key(category.id, position), allocated)
+                })
+                .collect(),
+        }
+    }
+
+    fn process_budget(&self) -> BudgetAllocation {
+        let budget = &self.budget;
+
+        let bracket_breakdown = match budget.mode {
+            BudgetMode::Percent => Vec::new(),
+            BudgetMode::Brackets => budget.bracket_breakdown(),
+        };
+
+        let computed_value = match budget.mode {
+            BudgetMode::Percent => (budget.income() * budget.donation_percent).round_dp(2),
+            BudgetMode::Brackets => bracket_breakdown
+                .iter()
+                .map(|bracket| bracket.donation_value)
+                .sum(),
+        };
+
+        BudgetAllocation {
+            donation_value: budget.value_override.unwrap_or(computed_value),
+            bracket_breakdown,
+        }
+    }
+
+    /// One allocation per category, in list order.
+    fn process_categories(&self, budget_value: Decimal) -> Vec<CategoryAllocation> {
+        let mut value_override_sum = Decimal::default();
+        let mut percent_override_sum = Decimal::default();
+
+        let mut multiplier_categories = Vec::new();
+
+        let mut allocated: Vec<CategoryAllocation> = self
+            .categories
+            .iter()
+            .enumerate()
+            .map(|(index, category)| {
+                let mut allocated = CategoryAllocation {
+                    included: category.enabled && !category.entries.is_empty(),
+                    ..Default::default()
+                };
+
+                if !allocated.included {
+                    return allocated;
+                }
+
+                if let Some(value_override) = category.value_override {
+                    allocated.donation_value = value_override;
+                    allocated.donation_percent = fraction_of(value_override, budget_value);
+
+                    value_override_sum += value_override;
+                    percent_override_sum += allocated.donation_percent;
+                } else if let Some(percent_override) = category.percent_override {
+                    allocated.donation_value = (percent_override * budget_value).round_dp(2);
+                    allocated.donation_percent = percent_override;
+
+                    value_override_sum += allocated.donation_value;
+                    percent_override_sum += percent_override;
+                } else {
+                    multiplier_categories.push(index);
+                }
+
+                allocated
+            })
+            .collect();
+
+        // Overrides beyond the budget leave nothing for the other categories, never less.
+        let left_over_cash = (budget_value - value_override_sum).max(Decimal::ZERO);
+        let left_over_percent = (Decimal::ONE - percent_override_sum).max(Decimal::ZERO);
+
+        let multipliers: Vec<Decimal> = multiplier_categories
+            .iter()
+            .map(|index| self.categories[*index].multiplier)
+            .collect();
+        let values = apportion(left_over_cash, &multipliers);
+        let percents = apportion(left_over_percent, &multipliers);
+
+        multiplier_categories
+            .into_iter()
+            .zip(values.into_iter().zip(percents))
+            .for_each(|(index, (value, percent))| {
+                allocated[index].donation_value = value;
+                allocated[index].donation_percent = percent;
+            });
+
+        self.categories
+            .iter()
+            .zip(&mut allocated)
+            .for_each(|(category, allocated)| List::process_category_entries(category, allocated));
+
+        allocated
+    }
+
+    fn process_category_entries(category: &Category, allocated: &mut CategoryAllocation) {
+        let category_value = allocated.donation_value;
+        let mut value_override_sum = Decimal::default();
+        let mut percent_override_sum = Decimal::default();
+
+        let mut multiplier_entries = Vec::new();
+
+        let mut entries = vec![EntryAllocation::default(); category.entries.len()];
+        if allocated.included {
+            category
+                .entries
+                .iter()
+                .zip(&mut entries)
+                .enumerate()
+                .filter(|(_, (entry, _))| entry.enabled)
+                .for_each(|(index, (entry, allocated_entry))| {
+                    if let Some(value_override) = entry.value_override {
+                        allocated_entry.donation_value = value_override;
+                        allocated_entry.donation_percent =
+                            fraction_of(value_override, category_value);
+
+                        value_override_sum += value_override;
+                        percent_override_sum += allocated_entry.donation_percent;
+
+                        allocated.has_entry_overrides = true;
+                    } else if let Some(percent_override) = entry.percent_override {
+                        allocated_entry.donation_value =
+                            (percent_override * category_value).round_dp(2);
+                        allocated_entry.donation_percent = percent_override;
+
+                        value_override_sum += allocated_entry.donation_value;
+                        percent_override_sum += percent_override;
+
+                        allocated.has_entry_overrides = true;
+                    } else {
+                        multiplier_entries.push(index);
+                    }
+                });
+        }
+
+        let left_over_cash = (category_value - value_override_sum).max(Decimal::ZERO);
+        let left_over_percent = (Decimal::ONE - percent_override_sum).max(Decimal::ZERO);
+
+        let multipliers: Vec<Decimal> = multiplier_entries
+            .iter()
+            .map(|index| category.entries[*index].multiplier)
+            .collect();
+        let values = apportion(left_over_cash, &multipliers);
+        let percents = apportion(left_over_percent, &multipliers);
+
+        multiplier_entries
+            .into_iter()
+            .zip(values.into_iter().zip(percents))
+            .for_each(|(index, (value, percent))| {
+                entries[index].donation_value = value;
+                entries[index].donation_percent = percent;
+            });
+
+        allocated.entries = category
+            .entries
+            .iter()
+            .zip(entries)
+            .enumerate()
+            .map(|(position, (entry, allocated))| (row_key(entry.id, position), allocated))
+            .collect();
+    }
+}
+
+/// `part` as a fraction of `whole` rounded to whole percents, zero when `whole` is.
+fn fraction_of(part: Decimal, whole: Decimal) -> Decimal {
+    if whole.is_zero() {
+        return Decimal::ZERO;
+    }
+
+    (part / whole).round_dp(2)
+}
+
+/// Splits `total` in proportion to `weights`, in steps of 0.01. The shares always add up to
+/// `total`: each is rounded down and the steps left over go to the largest remainders, the
+/// earlier share first on ties. Negative weights count as zero, and when every weight is zero
+/// the shares are equal.
+fn apportion(total: Decimal, weights: &[Decimal]) -> Vec<Decimal> {
+    let step = Decimal::new(1, 2);
+
+    if weights.is_empty() || total <= Decimal::ZERO {
+        return vec![Decimal::ZERO; weights.len()];
+    }
+
+    let weights: Vec<Decimal> = weights
+        .iter()
+        .map(|weight| (*weight).max(Decimal::ZERO))
+        .collect();
+    let weight_sum: Decimal = weights.iter().sum();
+    let exact: Vec<Decimal> = weights
+        .iter()
+        .map(|weight| {
+            if weight_sum.is_zero() {
+                total / Decimal::from(weights.len())
+            } else {
+                total * weight / weight_sum
+            }
+        })
+        .collect();
+    let mut shares: Vec<Decimal> = exact
+        .iter()
+        .map(|share| (share / step).floor() * step)
+        .collect();
+
+    // A stable sort, so ties keep their order.
+    let mut by_remainder: Vec<usize> = (0..shares.len()).collect();
+    by_remainder.sort_by(|a, b| (exact[*b] - shares[*b]).cmp(&(exact[*a] - shares[*a])));
+
+    let mut left_over = total - shares.iter().sum::<Decimal>();
+    for index in &by_remainder {
+        if left_over < step {
+            break;
+        }
+        shares[*index] += step;
+        left_over -= step;
+    }
+    // Whatever is below a step when `total` itself has finer digits.
+    shares[by_remainder[0]] += left_over;
+
+    shares
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Category, Entry, List};
+
+/// What a save server function returns. A conflict carries the stored list so the client
+/// can merge its edits or reload.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum SaveOutcome {
+    Saved(List),
+    Conflict(List),
+}
+
+impl List {
+    /// Merges the local edits in `self`, read at `self.version`, onto the `server` list of a
+    /// conflict. Rows the server changed since `self.version` keep the server values, every
+    /// other local edit, addition and deletion is kept. The result can be saved again.
+    pub fn merge_onto(&self, server: &List) -> List {
+        let base = self.version;
+
+        let budget = if server.budget.version > base {
+            server.budget.clone()
+        } else {
+            super::Budget {
+                id: server.budget.id,
+                list_id: server.budget.list_id,
+                version: server.budget.version,
+                ..self.budget.clone()
+            }
+        };
+
+        let mut categories: Vec<Category> = server
+            .categories
+            .iter()
+            .filter_map(|server_category| {
+                let local = self
+                    .categories
+                    .iter()
+                    .find(|local| local.id == server_category.id);
+
+                match local {
+                    Some(local) => Some(merge_category(local, server_category, base)),
+                    // Deleted locally, unless the server changed it since.
+                    None if category_changed_since(server_category, base) => {
+                        Some(server_category.clone())
+                    }
+                    None => None,
+                }
+            })
+            .collect();
+
+        // Local categories that are not on the server were either added locally, id 0, or
+        // deleted on the server. Added ones join a server category with the same name.
+        for local in self.categories.iter().filter(|local| local.id == 0) {
+            match categories
+                .iter_mut()
+                .find(|merged| merged.name.eq_ignore_ascii_case(&local.name))
+            {
+                Some(merged) => {
+                    for entry in &local.entries {
+                        if !merged.entries.iter().any(|merged| merged.ein == entry.ein) {
+                            merged.entries.push(Entry {
+                                id: 0,
+                                category_id: merged.id,
+                                ..entry.clone()
+                            });
+                        }
+                    }
+                }
+                None => categories.push(local.clone()),
+            }
+        }
+
+        List {
+            id: server.id,
+            user_id: server.user_id.clone(),
+            scenario_of: server.scenario_of,
+            name: server.name.clone(),
+            version: server.version,
+            budget,
+            categories,
+        }
+    }
+}
+
+fn category_changed_since(category: &Category, base: i32) -> bool {
+    category.version > base || category.entries.iter().any(|entry| entry.version > base)
+}
+
+fn merge_category(local: &Category, server: &Category, base: i32) -> Category {
+    let mut entries: Vec<Entry> = server
+        .entries
+        .iter()
+        .filter_map(|server_entry| {
+            match local
+                .entries
+                .iter()
+                .find(|local| local.id == server_entry.id)
+            {
+                Some(_) if server_entry.version > base => Some(server_entry.clone()),
+                Some(local_entry) => Some(Entry {
+                    version: server_entry.version,
+                    ..local_entry.clone()
+                }),
+                None if server_entry.version > base => Some(server_entry.clone()),
+                None => None,
+            }
+        })
+        .collect();
+
+    for local_entry in local.entries.iter().filter(|entry| entry.id == 0) {
+        if !entries.iter().any(|entry| entry.ein == local_entry.ein) {
+            entries.push(local_entry.clone());
+        }
+    }
+
+    let fields = if server.version > base { server } else { local };
+
+    Category {
+        id: server.id,
+        list_id: server.list_id,
+        version: server.version,
+        entries,
+        ..fields.clone()
+    }
+}
@@ -0,0 +1,478 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum EventEntity {
+    Budget,
+    GivingBracket,
+    Category,
+    Entry,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum EventAction {
+    Insert,
+    Update,
+    Delete,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum EventState {
+    Applied,
+    Undone,
+    /// Undone and then replaced by a newer change, so it can no longer be redone.
+    Discarded,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum EventOrigin {
+    Save,
+    Undo,
+    Redo,
+}
+
+/// One row written by a `List::save`, `List::undo` or `List::redo`. `actor` is the Clerk user
+/// id of whoever made the change.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ListEvent {
+    pub id: i32,
+    pub list_id: i32,
+
+    pub change_set: i32,
+    pub actor: String,
+    pub created_at: DateTime<Utc>,
+
+    pub entity: EventEntity,
+    pub entity_id: i32,
+    pub action: EventAction,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+
+    pub state: EventState,
+    pub origin: EventOrigin,
+    /// The change set an undo or redo reverted or replayed.
+    pub origin_change_set: Option<i32>,
+}
+
+use cfg_if::cfg_if;
+cfg_if! { if #[cfg(feature = "ssr")] {
+use sea_orm::{prelude::Json, sea_query::Expr, *};
+use serde::de::DeserializeOwned;
+
+use crate::entity::budget::ActiveModel as ActiveBudgetModel;
+use crate::entity::category::ActiveModel as ActiveCategoryModel;
+use crate::entity::entry::ActiveModel as ActiveEntryModel;
+use crate::entity::giving_bracket::ActiveModel as ActiveGivingBracketModel;
+use crate::entity::list_event::{
+    ActiveModel as ActiveListEventModel, Column as ListEventColumn, Entity as ListEventEntity,
+    EventAction as EventActionModel, EventEntity as EventEntityModel,
+    EventOrigin as EventOriginModel, EventState as EventStateModel, Model as ListEventModel,
+};
+use super::{List, SaveError};
+
+impl From<EventEntityModel> for EventEntity {
+    fn from(value: EventEntityModel) -> Self {
+        match value {
+            EventEntityModel::Budget => EventEntity::Budget,
+            EventEntityModel::GivingBracket => EventEntity::GivingBracket,
+            EventEntityModel::Category => EventEntity::Category,
+            EventEntityModel::Entry => EventEntity::Entry,
+        }
+    }
+}
+
+impl From<EventActionModel> for EventAction {
+    fn from(value: EventActionModel) -> Self {
+        match value {
+            EventActionModel::Insert => EventAction::Insert,
+            EventActionModel::Update => EventAction::Update,
+            EventActionModel::Delete => EventAction::Delete,
+        }
+    }
+}
+
+impl From<EventStateModel> for EventState {
+    fn from(value: EventStateModel) -> Self {
+        match value {
+            EventStateModel::Applied => EventState::Applied,
+            EventStateModel::Undone => EventState::Undone,
+            EventStateModel::Discarded => EventState::Discarded,
+        }
+    }
+}
+
+impl From<EventOriginModel> for EventOrigin {
+    fn from(value: EventOriginModel) -> Self {
+        match value {
+            EventOriginModel::Save => EventOrigin::Save,
+            EventOriginModel::Undo => EventOrigin::Undo,
+            EventOriginModel::Redo => EventOrigin::Redo,
+        }
+    }
+}
+
+impl From<ListEventModel> for ListEvent {
+    fn from(value: ListEventModel) -> Self {
+        Self {
+            id: value.id,
+            list_id: value.list_id,
+            change_set: value.change_set,
+            actor: value.actor,
+            created_at: value.created_at,
+            entity: value.entity.into(),
+            entity_id: value.entity_id,
+            action: value.action.into(),
+            before: value.before,
+            after: value.after,
+            state: value.state.into(),
+            origin: value.origin.into(),
+            origin_change_set: value.origin_change_set,
+        }
+    }
+}
+
+struct RecordedChange {
+    entity: EventEntityModel,
+    entity_id: i32,
+    action: EventActionModel,
+    before: Option<Json>,
+    after: Option<Json>,
+}
+
+/// Collects the row changes of one save so they can be written as a single change set.
+#[derive(Default)]
+pub(super) struct ChangeRecorder {
+    changes: Vec<RecordedChange>,
+    /// Set when recording an undo or redo of the given change set instead of a save.
+    replaying: Option<(EventOriginModel, i32)>,
+}
+
+fn to_json<M: Serialize>(model: &M) -> Result<Json, DbErr> {
+    serde_json::to_value(model).map_err(|error| DbErr::Json(error.to_string()))
+}
+
+impl ChangeRecorder {
+    fn replaying(origin: EventOriginModel, change_set: i32) -> Self {
+        Self {
+            changes: Vec::new(),
+            replaying: Some((origin, change_set)),
+        }
+    }
+
+    pub(super) fn insert<M: Serialize>(
+        &mut self,
+        entity: EventEntityModel,
+        entity_id: i32,
+        after: &M,
+    ) -> Result<(), DbErr> {
+        self.changes.push(RecordedChange {
+            entity,
+            entity_id,
+            action: EventActionModel::Insert,
+            before: None,
+            after: Some(to_json(after)?),
+        });
+        Ok(())
+    }
+
+    /// Skips updates that did not change any column.
+    pub(super) fn update<M: Serialize>(
+        &mut self,
+        entity: EventEntityModel,
+        entity_id: i32,
+        before: &M,
+        after: &M,
+    ) -> Result<(), DbErr> {
+        let (before, after) = (to_json(before)?, to_json(after)?);
+        if before != after {
+            self.changes.push(RecordedChange {
+                entity,
+                entity_id,
+                action: EventActionModel::Update,
+                before: Some(before),
+                after: Some(after),
+            });
+        }
+        Ok(())
+    }
+
+    pub(super) fn delete<M: Serialize>(
+        &mut self,
+        entity: EventEntityModel,
+        entity_id: i32,
+        before: &M,
+    ) -> Result<(), DbErr> {
+        self.changes.push(RecordedChange {
+            entity,
+            entity_id,
+            action: EventActionModel::Delete,
+            before: Some(to_json(before)?),
+            after: None,
+        });
+        Ok(())
+    }
+
+    /// Records a row restored by an undo or redo, as the action that took it from `before`
+    /// to `after`.
+    fn restore(
+        &mut self,
+        entity: EventEntityModel,
+        entity_id: i32,
+        before: Option<Json>,
+        after: Option<Json>,
+    ) {
+        let action = match (&before, &after) {
+            (None, None) => return,
+            (None, Some(_)) => EventActionModel::Insert,
+            (Some(_), None) => EventActionModel::Delete,
+            (Some(_), Some(_)) => EventActionModel::Update,
+        };
+        self.changes.push(RecordedChange {
+            entity,
+            entity_id,
+            action,
+            before,
+            after,
+        });
+    }
+
+    /// Writes the recorded changes as the next change set of the list. A save discards the
+    /// undone change sets, a new change ends the redo history like in any editor.
+    pub(super) async fn write<C: ConnectionTrait>(
+        self,
+        db: &C,
+        list_id: i32,
+        actor: &str,
+    ) -> Result<(), DbErr> {
+        if self.changes.is_empty() {
+            return Ok(());
+        }
+
+        if self.replaying.is_none() {
+            ListEventEntity::update_many()
+                .col_expr(ListEventColumn::State, Expr::value(EventStateModel::Discarded))
+                .filter(ListEventColumn::ListId.eq(list_id))
+                .filter(ListEventColumn::State.eq(EventStateModel::Undone))
+                .exec(db)
+                .await?;
+        }
+
+        let change_set = ListEventEntity::find()
+            .filter(ListEventColumn::ListId.eq(list_id))
+            .order_by_desc(ListEventColumn::ChangeSet)
+            .one(db)
+            .await?
+            .map_or(1, |event| event.change_set + 1);
+        let created_at = chrono::Utc::now();
+        let (origin, origin_change_set) = match self.replaying {
+            Some((origin, change_set)) => (origin, Some(change_set)),
+            None => (EventOriginModel::Save, None),
+        };
+
+        ListEventEntity::insert_many(self.changes.into_iter().map(|change| {
+            ActiveListEventModel {
+                id: NotSet,
+                list_id: Set(list_id),
+                change_set: Set(change_set),
+                actor: Set(actor.to_owned()),
+                created_at: Set(created_at),
+                entity: Set(change.entity),
+                entity_id: Set(change.entity_id),
+                action: Set(change.action),
+                before: Set(change.before),
+                after: Set(change.after),
+                state: Set(EventStateModel::Applied),
+                origin: Set(origin),
+                origin_change_set: Set(origin_change_set),
+            }
+        }))
+        .exec(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Moves a row from the `current` snapshot to the `target` snapshot, inserting or deleting
+/// it when one of them is missing. Versioned rows are stamped with the list `version` of the
+/// undo or redo, so conflict merges see them as changed. Returns the row as written.
+async fn restore_row<A>(
+    txn: &DatabaseTransaction,
+    current: Option<&Json>,
+    target: Option<&Json>,
+    version: i32,
+) -> Result<Option<Json>, DbErr>
+where
+    A: ActiveModelTrait + ActiveModelBehavior + Send,
+    <A::Entity as EntityTrait>::Model: DeserializeOwned + IntoActiveModel<A>,
+{
+    let target = target.map(|json| {
+        let mut json = json.clone();
+        if let Some(row_version) = json.get_mut("version") {
+            *row_version = version.into();
+        }
+        json
+    });
+    let model = |json: &Json| {
+        serde_json::from_value::<<A::Entity as EntityTrait>::Model>(json.clone())
+            .map_err(|error| DbErr::Json(error.to_string()))
+    };
+
+    match (current, target.as_ref()) {
+        (Some(current), None) => {
+            model(current)?.into_active_model().delete(txn).await?;
+        }
+        (None, Some(target)) => {
+            model(target)?.into_active_model().reset_all().insert(txn).await?;
+        }
+        (Some(_), Some(target)) => {
+            model(target)?.into_active_model().reset_all().update(txn).await?;
+        }
+        (None, None) => {}
+    }
+
+    Ok(target)
+}
+
+async fn restore_event(
+    txn: &DatabaseTransaction,
+    event: &ListEventModel,
+    current: Option<&Json>,
+    target: Option<&Json>,
+    version: i32,
+) -> Result<Option<Json>, DbErr> {
+    match event.entity {
+        EventEntityModel::Budget => {
+            restore_row::<ActiveBudgetModel>(txn, current, target, version).await
+        }
+        EventEntityModel::GivingBracket => {
+            restore_row::<ActiveGivingBracketModel>(txn, current, target, version).await
+        }
+        EventEntityModel::Category => {
+            restore_row::<ActiveCategoryModel>(txn, current, target, version).await
+        }
+        EventEntityModel::Entry => {
+            restore_row::<ActiveEntryModel>(txn, current, target, version).await
+        }
+    }
+}
+
+impl List {
+    /// Every recorded change of the list, newest first.
+    pub async fn history(db: &DatabaseConnection, list_id: i32) -> Result<Vec<ListEvent>, DbErr> {
+        Ok(ListEventEntity::find()
+            .filter(ListEventColumn::ListId.eq(list_id))
+            .order_by_desc(ListEventColumn::Id)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|event| event.into())
+            .collect())
+    }
+
+    /// Reverts the newest applied change set and records the reverted rows as an undo by
+    /// `actor`. Returns `None` when there is nothing to undo.
+    ///
+    /// Fails with `SaveError::Conflict` when the list was saved since `self.version` was read.
+    pub async fn undo(
+        &self,
+        db: &DatabaseConnection,
+        actor: &str,
+    ) -> Result<Option<List>, SaveError> {
+        let txn = db.begin().await?;
+        // Returning early drops `txn`, which rolls the claimed version back.
+        let version = self.claim_version(&txn).await?;
+
+        let Some(last) = ListEventEntity::find()
+            .filter(ListEventColumn::ListId.eq(self.id))
+            .filter(ListEventColumn::Origin.eq(EventOriginModel::Save))
+            .filter(ListEventColumn::State.eq(EventStateModel::Applied))
+            .order_by_desc(ListEventColumn::ChangeSet)
+            .one(&txn)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        // Inverse events run newest first, so deleted categories come back before their entries.
+        let events = ListEventEntity::find()
+            .filter(ListEventColumn::ListId.eq(self.id))
+            .filter(ListEventColumn::ChangeSet.eq(last.change_set))
+            .order_by_desc(ListEventColumn::Id)
+            .all(&txn)
+            .await?;
+        let mut recorder = ChangeRecorder::replaying(EventOriginModel::Undo, last.change_set);
+        for event in events {
+            let restored =
+                restore_event(&txn, &event, event.after.as_ref(), event.before.as_ref(), version)
+                    .await?;
+            recorder.restore(event.entity, event.entity_id, event.after, restored);
+        }
+
+        set_change_set_state(&txn, self.id, last.change_set, EventStateModel::Undone).await?;
+        recorder.write(&txn, self.id, actor).await?;
+        txn.commit().await?;
+
+        Ok(List::from_id(db, self.id).await)
+    }
+
+    /// Replays the oldest undone change set and records the replayed rows as a redo by
+    /// `actor`. Returns `None` when there is nothing to redo.
+    ///
+    /// Fails with `SaveError::Conflict` when the list was saved since `self.version` was read.
+    pub async fn redo(
+        &self,
+        db: &DatabaseConnection,
+        actor: &str,
+    ) -> Result<Option<List>, SaveError> {
+        let txn = db.begin().await?;
+        let version = self.claim_version(&txn).await?;
+
+        let Some(next) = ListEventEntity::find()
+            .filter(ListEventColumn::ListId.eq(self.id))
+            .filter(ListEventColumn::State.eq(EventStateModel::Undone))
+            .order_by_asc(ListEventColumn::ChangeSet)
+            .one(&txn)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let events = ListEventEntity::find()
+            .filter(ListEventColumn::ListId.eq(self.id))
+            .filter(ListEventColumn::ChangeSet.eq(next.change_set))
+            .order_by_asc(ListEventColumn::Id)
+            .all(&txn)
+            .await?;
+        let mut recorder = ChangeRecorder::replaying(EventOriginModel::Redo, next.change_set);
+        for event in events {
+            let restored =
+                restore_event(&txn, &event, event.before.as_ref(), event.after.as_ref(), version)
+                    .await?;
+            recorder.restore(event.entity, event.entity_id, event.before, restored);
+        }
+
+        set_change_set_state(&txn, self.id, next.change_set, EventStateModel::Applied).await?;
+        recorder.write(&txn, self.id, actor).await?;
+        txn.commit().await?;
+
+        Ok(List::from_id(db, self.id).await)
+    }
+}
+
+async fn set_change_set_state(
+    txn: &DatabaseTransaction,
+    list_id: i32,
+    change_set: i32,
+    state: EventStateModel,
+) -> Result<(), DbErr> {
+    ListEventEntity::update_many()
+        .col_expr(ListEventColumn::State, Expr::value(state))
+        .filter(ListEventColumn::ListId.eq(list_id))
+        .filter(ListEventColumn::ChangeSet.eq(change_set))
+        .exec(txn)
+        .await?;
+
+    Ok(())
+}
+}}
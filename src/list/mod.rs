@@ -1,17 +1,44 @@
-use std::ops::ControlFlow;
-
 use rust_decimal::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize)]
+pub mod allocation;
+pub mod conflict;
+pub mod history;
+pub mod scenario;
+pub mod transfer;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct List {
     pub id: i32,
     pub user_id: String,
+    /// Set on scenarios to the id of the live list they were forked from.
+    pub scenario_of: Option<i32>,
+    pub name: Option<String>,
+    /// The stored version this list was read at, `save` refuses to overwrite newer changes.
+    pub version: i32,
     pub budget: Budget,
     pub categories: Vec<Category>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub enum BudgetMode {
+    /// `donation_percent` of the income.
+    #[default]
+    Percent,
+    /// Marginal giving brackets applied to the income.
+    Brackets,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub enum IncomeBasis {
+    /// `total_value`.
+    #[default]
+    Gross,
+    /// `net_value`, falling back to `total_value` when it is not set.
+    Net,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Budget {
     pub id: i32,
     pub list_id: i32,
@@ -20,10 +47,25 @@ pub struct Budget {
     pub donation_percent: Decimal,
     pub value_override: Option<Decimal>,
 
-    pub temp_donation_value: Decimal,
+    pub mode: BudgetMode,
+    pub income_basis: IncomeBasis,
+    pub net_value: Option<Decimal>,
+    pub brackets: Vec<GivingBracket>,
+
+    pub version: i32,
+}
+
+/// Gives `percent` of the income above `threshold`, up to the next bracket's threshold.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct GivingBracket {
+    pub id: i32,
+    pub budget_id: i32,
+
+    pub threshold: Decimal,
+    pub percent: Decimal,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Category {
     pub id: i32,
     pub list_id: i32,
@@ -37,14 +79,10 @@ pub struct Category {
 
     pub enabled: bool,
 
-    pub temp_donation_value: Decimal,
-    pub temp_donation_percent: Decimal,
-
-    pub temp_included: bool,
-    pub temp_has_entry_overrides: bool,
+    pub version: i32,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Entry {
     pub id: i32,
     pub category_id: i32,
@@ -57,209 +95,25 @@ pub struct Entry {
 
     pub enabled: bool,
 
-    pub temp_donation_value: Decimal,
-    pub temp_donation_percent: Decimal,
+    pub version: i32,
 }
 
-impl List {
-    pub fn is_list_valid(self) -> bool {
-        let mut valid = true;
-
-        if self.categories.is_empty() {
-            return valid;
-        }
-
-        let mut categories_value_sum = Decimal::default();
-        let mut categories_percent_sum = Decimal::default();
-        let mut entry_count = 0;
-
-        self.categories
-            .iter()
-            .filter(|category| category.temp_included)
-            .filter(|category| !category.entries.is_empty())
-            .try_for_each(|category| {
-                if category.temp_donation_value == Decimal::ZERO
-                    || category.temp_donation_percent == Decimal::ZERO
-                {
-                    valid = false;
-                    return ControlFlow::Break(());
-                }
-
-                categories_value_sum += category.temp_donation_value;
-                categories_percent_sum += category.temp_donation_percent;
-                entry_count += category.entries.len();
-
-                let mut entries_value_sum = Decimal::default();
-                let mut entries_percent_sum = Decimal::default();
-
-                category
-                    .entries
-                    .iter()
-                    .filter(|entry| entry.enabled)
-                    .try_for_each(|entry| {
-                        if entry.temp_donation_value == Decimal::ZERO
-                            || entry.temp_donation_percent == Decimal::ZERO
-                        {
-                            valid = false;
-                            return ControlFlow::Break(());
-                        }
-
-                        entries_value_sum += entry.temp_donation_value;
-                        entries_percent_sum += entry.temp_donation_percent;
-                        ControlFlow::Continue(())
-                    });
-
-                let entries_value_overflow =
-                    (entries_value_sum - category.temp_donation_value).abs() > Decimal::ONE;
-                let entries_percent_overflow =
-                    (entries_percent_sum - Decimal::ONE).abs() > Decimal::new(1, 2);
-
-                if entries_value_overflow || entries_percent_overflow {
-                    valid = false;
-                    return ControlFlow::Break(());
-                }
-
-                ControlFlow::Continue(())
-            });
-
-        if !valid || entry_count == 0 {
-            return valid;
-        }
-
-        let categories_value_overflow =
-            (categories_value_sum - self.budget.temp_donation_value).abs() > Decimal::ONE;
-        let categories_percent_overflow =
-            (categories_percent_sum - Decimal::ONE).abs() > Decimal::new(1, 2);
-
-        if categories_value_overflow || categories_percent_overflow {
-            valid = false;
+impl Budget {
+    pub fn income(&self) -> Decimal {
+        match self.income_basis {
+            IncomeBasis::Gross => self.total_value,
+            IncomeBasis::Net => self.net_value.unwrap_or(self.total_value),
         }
-
-        valid
-    }
-
-    pub fn process_list(&mut self) {
-        self.process_budget();
-        self.process_categories();
-        self.process_entries();
-    }
-
-    fn process_budget(&mut self) {
-        self.budget.temp_donation_value = self
-            .budget
-            .value_override
-            .unwrap_or((self.budget.total_value * self.budget.donation_percent).round_dp(2));
-    }
-
-    fn process_categories(&mut self) {
-        let mut multiplier_sum = Decimal::default();
-        let mut value_override_sum = Decimal::default();
-        let mut percent_override_sum = Decimal::default();
-
-        let mut multiplier_categories = Vec::new();
-
-        self.categories.iter_mut().for_each(|category| {
-            category.temp_included = category.enabled && !category.entries.is_empty();
-
-            if !category.temp_included {
-                return;
-            }
-
-            if let Some(value_override) = category.value_override {
-                category.temp_donation_value = value_override;
-                category.temp_donation_percent =
-                    (value_override / self.budget.temp_donation_value).round_dp(2);
-
-                value_override_sum += value_override;
-                percent_override_sum += category.temp_donation_percent;
-            } else if let Some(percent_override) = category.percent_override {
-                category.temp_donation_value =
-                    (percent_override * self.budget.temp_donation_value).round_dp(2);
-                category.temp_donation_percent = percent_override;
-
-                value_override_sum += category.temp_donation_value;
-                percent_override_sum += percent_override;
-            } else {
-                multiplier_sum += category.multiplier;
-                multiplier_categories.push(category);
-            }
-        });
-
-        let left_over_cash = self.budget.temp_donation_value - value_override_sum;
-        let left_over_percent = Decimal::default() - percent_override_sum;
-
-        multiplier_categories.iter_mut().for_each(|category| {
-            let category_multiplier_adjustment = (category.multiplier / multiplier_sum).round_dp(2);
-
-            category.temp_donation_value =
-                (left_over_cash * category_multiplier_adjustment).round_dp(2);
-            category.temp_donation_percent =
-                (left_over_percent * category_multiplier_adjustment).round_dp(2);
-        });
-    }
-
-    fn process_entries(&mut self) {
-        self.categories
-            .iter_mut()
-            .for_each(|category| List::process_category_entries(category));
-    }
-
-    fn process_category_entries(category: &mut Category) {
-        let mut multiplier_sum = Decimal::default();
-        let mut value_override_sum = Decimal::default();
-        let mut percent_override_sum = Decimal::default();
-
-        let mut multiplier_entries = Vec::new();
-
-        category
-            .entries
-            .iter_mut()
-            .filter(|entry| entry.enabled && category.enabled)
-            .for_each(|entry| {
-                if let Some(value_override) = entry.value_override {
-                    entry.temp_donation_value = value_override;
-                    entry.temp_donation_percent =
-                        (value_override / category.temp_donation_value).round_dp(2);
-
-                    value_override_sum += value_override;
-                    percent_override_sum += entry.temp_donation_percent;
-
-                    category.temp_has_entry_overrides = true;
-                } else if let Some(percent_override) = entry.percent_override {
-                    entry.temp_donation_value =
-                        (percent_override * category.temp_donation_value).round_dp(2);
-                    entry.temp_donation_percent = percent_override;
-
-                    value_override_sum += entry.temp_donation_value;
-                    percent_override_sum += percent_override;
-
-                    category.temp_has_entry_overrides = true;
-                } else {
-                    multiplier_sum += entry.multiplier;
-                    multiplier_entries.push(entry);
-                }
-            });
-
-        let left_over_cash = category.temp_donation_value - value_override_sum;
-        let left_over_percent = Decimal::default() - percent_override_sum;
-
-        multiplier_entries.iter_mut().for_each(|entry| {
-            let entry_multiplier_adjustment = (entry.multiplier / multiplier_sum).round_dp(2);
-
-            entry.temp_donation_value = (left_over_cash * entry_multiplier_adjustment).round_dp(2);
-            entry.temp_donation_percent =
-                (left_over_percent * entry_multiplier_adjustment).round_dp(2);
-        });
     }
 }
 
 use cfg_if::cfg_if;
 cfg_if! { if #[cfg(feature = "ssr")] {
-use sea_orm::*;
+use sea_orm::{sea_query::Expr, *};
 
 use crate::entity::budget::{
-    ActiveModel as ActiveBudgetModel, Column as BudgetColumn, Entity as BudgetEntity,
-    Model as BudgetModel,
+    ActiveModel as ActiveBudgetModel, BudgetMode as BudgetModeModel, Column as BudgetColumn,
+    Entity as BudgetEntity, IncomeBasis as IncomeBasisModel, Model as BudgetModel,
 };
 use crate::entity::category::{
     ActiveModel as ActiveCategoryModel, Column as CategoryColumn, Entity as CategoryEntity,
@@ -269,15 +123,53 @@ use crate::entity::entry::{
     ActiveModel as ActiveEntryModel, Column as EntryColumn, Entity as EntryEntity,
     Model as EntryModel,
 };
+use crate::entity::giving_bracket::{
+    ActiveModel as ActiveGivingBracketModel, Column as GivingBracketColumn,
+    Entity as GivingBracketEntity, Model as GivingBracketModel,
+};
 use crate::entity::list::{
     ActiveModel as ActiveListModel, Column as ListColumn, Entity as ListEntity, Model as ListModel,
 };
+use crate::entity::list_event::EventEntity as EventEntityModel;
+
+use history::ChangeRecorder;
+
+#[derive(Debug)]
+pub enum SaveError {
+    /// Someone saved the list after it was read. Carries the stored list.
+    Conflict(Box<List>),
+    Database(DbErr),
+}
+
+impl From<DbErr> for SaveError {
+    fn from(value: DbErr) -> Self {
+        SaveError::Database(value)
+    }
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Conflict(stored) => write!(
+                f,
+                "List {} was changed by someone else, it is now at version {}.",
+                stored.id, stored.version
+            ),
+            SaveError::Database(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
 
 impl From<(ListModel, Budget, Vec<Category>)> for List {
     fn from(value: (ListModel, Budget, Vec<Category>)) -> Self {
         Self {
             id: value.0.id,
             user_id: value.0.user_id,
+            scenario_of: value.0.scenario_of,
+            name: value.0.name,
+            version: value.0.version,
             budget: value.1,
             categories: value.2,
         }
@@ -289,19 +181,62 @@ impl From<&List> for ListModel {
         Self {
             id: value.id,
             user_id: value.user_id.clone(),
+            scenario_of: value.scenario_of,
+            name: value.name.clone(),
+            version: value.version,
+        }
+    }
+}
+
+impl From<BudgetModeModel> for BudgetMode {
+    fn from(value: BudgetModeModel) -> Self {
+        match value {
+            BudgetModeModel::Percent => BudgetMode::Percent,
+            BudgetModeModel::Brackets => BudgetMode::Brackets,
+        }
+    }
+}
+
+impl From<BudgetMode> for BudgetModeModel {
+    fn from(value: BudgetMode) -> Self {
+        match value {
+            BudgetMode::Percent => BudgetModeModel::Percent,
+            BudgetMode::Brackets => BudgetModeModel::Brackets,
+        }
+    }
+}
+
+impl From<IncomeBasisModel> for IncomeBasis {
+    fn from(value: IncomeBasisModel) -> Self {
+        match value {
+            IncomeBasisModel::Gross => IncomeBasis::Gross,
+            IncomeBasisModel::Net => IncomeBasis::Net,
         }
     }
 }
 
-impl From<BudgetModel> for Budget {
-    fn from(value: BudgetModel) -> Self {
+impl From<IncomeBasis> for IncomeBasisModel {
+    fn from(value: IncomeBasis) -> Self {
+        match value {
+            IncomeBasis::Gross => IncomeBasisModel::Gross,
+            IncomeBasis::Net => IncomeBasisModel::Net,
+        }
+    }
+}
+
+impl From<(BudgetModel, Vec<GivingBracket>)> for Budget {
+    fn from(value: (BudgetModel, Vec<GivingBracket>)) -> Self {
         Self {
-            id: value.id,
-            list_id: value.list_id,
-            total_value: value.total_value,
-            donation_percent: value.donation_percent,
-            value_override: value.value_override,
-            temp_donation_value: Decimal::ZERO,
+            id: value.0.id,
+            list_id: value.0.list_id,
+            total_value: value.0.total_value,
+            donation_percent: value.0.donation_percent,
+            value_override: value.0.value_override,
+            mode: value.0.mode.into(),
+            income_basis: value.0.income_basis.into(),
+            net_value: value.0.net_value,
+            brackets: value.1,
+            version: value.0.version,
         }
     }
 }
@@ -314,6 +249,32 @@ impl From<&Budget> for BudgetModel {
             total_value: value.total_value,
             donation_percent: value.donation_percent,
             value_override: value.value_override,
+            mode: value.mode.into(),
+            income_basis: value.income_basis.into(),
+            net_value: value.net_value,
+            version: value.version,
+        }
+    }
+}
+
+impl From<GivingBracketModel> for GivingBracket {
+    fn from(value: GivingBracketModel) -> Self {
+        Self {
+            id: value.id,
+            budget_id: value.budget_id,
+            threshold: value.threshold,
+            percent: value.percent,
+        }
+    }
+}
+
+impl From<&GivingBracket> for GivingBracketModel {
+    fn from(value: &GivingBracket) -> Self {
+        Self {
+            id: value.id,
+            budget_id: value.budget_id,
+            threshold: value.threshold,
+            percent: value.percent,
         }
     }
 }
@@ -329,10 +290,7 @@ impl From<(CategoryModel, Vec<Entry>)> for Category {
             percent_override: value.0.percent_override,
             value_override: value.0.value_override,
             enabled: value.0.enabled,
-            temp_donation_value: Decimal::ZERO,
-            temp_donation_percent: Decimal::ZERO,
-            temp_included: false,
-            temp_has_entry_overrides: false,
+            version: value.0.version,
         }
     }
 }
@@ -347,6 +305,7 @@ impl From<&Category> for CategoryModel {
             percent_override: value.percent_override,
             value_override: value.value_override,
             enabled: value.enabled,
+            version: value.version,
         }
     }
 }
@@ -361,8 +320,7 @@ impl From<EntryModel> for Entry {
             percent_override: value.percent_override,
             value_override: value.value_override,
             enabled: value.enabled,
-            temp_donation_value: Decimal::ZERO,
-            temp_donation_percent: Decimal::ZERO,
+            version: value.version,
         }
     }
 }
@@ -377,22 +335,44 @@ impl From<&Entry> for EntryModel {
             percent_override: value.percent_override,
             value_override: value.value_override,
             enabled: value.enabled,
+            version: value.version,
         }
     }
 }
 
 impl List {
+    /// The live list of a user. Served by the `(user_id, scenario_of)` index, the budget,
+    /// categories and entries are then loaded through their foreign key indexes.
     pub async fn from_user_id(db: &DatabaseConnection, user_id: String) -> Option<List> {
         let list_model: ListModel = ListEntity::find()
             .filter(ListColumn::UserId.eq(user_id))
+            .filter(ListColumn::ScenarioOf.is_null())
+            .order_by_asc(ListColumn::Id)
             .one(db)
             .await
             .ok()??;
+
+        List::from_list_model(db, list_model).await
+    }
+
+    pub async fn from_id<C: ConnectionTrait>(db: &C, id: i32) -> Option<List> {
+        let list_model: ListModel = ListEntity::find_by_id(id).one(db).await.ok()??;
+
+        List::from_list_model(db, list_model).await
+    }
+
+    async fn from_list_model<C: ConnectionTrait>(db: &C, list_model: ListModel) -> Option<List> {
         let budget_model: BudgetModel = BudgetEntity::find()
             .filter(BudgetColumn::ListId.eq(list_model.id))
             .one(db)
             .await
             .ok()??;
+        let bracket_models: Vec<GivingBracketModel> = GivingBracketEntity::find()
+            .filter(GivingBracketColumn::BudgetId.eq(budget_model.id))
+            .order_by_asc(GivingBracketColumn::Threshold)
+            .all(db)
+            .await
+            .ok()?;
         let category_models: Vec<CategoryModel> = CategoryEntity::find()
             .filter(CategoryColumn::ListId.eq(list_model.id))
             .all(db)
@@ -407,10 +387,14 @@ impl List {
             .collect();
         let categories: Vec<Category> = category_models
             .into_iter()
-            .zip(entries.into_iter())
+            .zip(entries)
             .map(|(category, entries)| (category, entries).into())
             .collect();
-        let budget: Budget = budget_model.into();
+        let brackets: Vec<GivingBracket> = bracket_models
+            .into_iter()
+            .map(|bracket| bracket.into())
+            .collect();
+        let budget: Budget = (budget_model, brackets).into();
         let list: List = (list_model, budget, categories).into();
 
         Some(list)
@@ -420,6 +404,9 @@ impl List {
         let list = ActiveListModel {
             id: NotSet,
             user_id: Set(user_id.clone()),
+            scenario_of: Set(None),
+            name: Set(None),
+            version: Set(1),
         }
         .save(db)
         .await.ok()?;
@@ -430,25 +417,305 @@ impl List {
             total_value: Set(Decimal::new(50_000, 0)),
             donation_percent: Set(Decimal::new(10, 2)),
             value_override: Set(None),
+            mode: Set(BudgetModeModel::Percent),
+            income_basis: Set(IncomeBasisModel::Gross),
+            net_value: Set(None),
+            version: Set(1),
         }
         .save(db)
         .await.ok()?;
 
         List::from_user_id(db, user_id).await
     }
-    // fn save(self) {
-    //     let entry_models: Vec<Vec<EntryModel>> = self.categories.iter().map(|category| category.entries.iter().map(|entry| entry.into()).collect()).collect();
-    //     let category_models: Vec<CategoryModel> = self.categories.iter().map(|category| category.into()).collect();
-    //     let budget_model: BudgetModel = (&self.budget).into();
-    //     let list_model: ListModel = (&self).into();
 
-    //     let list_model: ActiveListModel = list_model.into();
-    //     let budget_model: ActiveBudgetModel = budget_model.into();
-    //     let category_models: Vec<ActiveCategoryModel> = category_models.into_iter().map(|category| category.into()).collect();
-    //     let entry_models: Vec<Vec<ActiveEntryModel>> = entry_models.into_iter().map(|category| category.into_iter().map(|entry| entry.into()).collect()).collect();
+    /// Moves the stored list from `self.version` to the next version and returns it. Every
+    /// write to the list claims a version first. The conditional update also locks the row,
+    /// so concurrent writes of the same version cannot both succeed.
+    pub(super) async fn claim_version(&self, txn: &DatabaseTransaction) -> Result<i32, SaveError> {
+        let version = self.version + 1;
+        let claimed = ListEntity::update_many()
+            .col_expr(ListColumn::Version, Expr::value(version))
+            .filter(ListColumn::Id.eq(self.id))
+            .filter(ListColumn::Version.eq(self.version))
+            .exec(txn)
+            .await?;
+        if claimed.rows_affected == 0 {
+            let stored = List::from_id(txn, self.id)
+                .await
+                .ok_or(DbErr::RecordNotFound("List.".to_owned()))?;
+            return Err(SaveError::Conflict(Box::new(stored)));
+        }
+
+        Ok(version)
+    }
+
+    /// Writes the budget, categories and entries of the list in one transaction and returns
+    /// the stored list. Rows missing from `self` are deleted. Categories and entries whose id
+    /// is not stored under this list are inserted as new rows, so client supplied ids can
+    /// never touch another user's data. Every changed row is recorded in the list history
+    /// under `actor`.
+    ///
+    /// Fails with `SaveError::Conflict` when the list was saved since `self.version` was read.
+    pub async fn save(&self, db: &DatabaseConnection, actor: &str) -> Result<List, SaveError> {
+        let txn = db.begin().await?;
+        let saved = self.save_in(&txn, actor).await?;
+        txn.commit().await?;
+
+        Ok(saved)
+    }
+
+    /// `save` inside a transaction the caller commits, for writes that must land together
+    /// with other changes. Nothing is written if the caller drops `txn` instead.
+    async fn save_in(&self, txn: &DatabaseTransaction, actor: &str) -> Result<List, SaveError> {
+        let mut recorder = ChangeRecorder::default();
+        let version = self.claim_version(txn).await?;
+
+        let budget_model: BudgetModel = BudgetEntity::find()
+            .filter(BudgetColumn::ListId.eq(self.id))
+            .one(txn)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Budget for list.".to_owned()))?;
+
+        let budget = BudgetModel {
+            id: budget_model.id,
+            list_id: budget_model.list_id,
+            version: budget_model.version,
+            ..(&self.budget).into()
+        };
+        if budget != budget_model {
+            let updated = BudgetModel { version, ..budget }
+                .into_active_model()
+                .reset_all()
+                .update(txn)
+                .await?;
+            recorder.update(
+                EventEntityModel::Budget,
+                budget_model.id,
+                &budget_model,
+                &updated,
+            )?;
+        }
+
+        let stored_brackets: Vec<GivingBracketModel> = GivingBracketEntity::find()
+            .filter(GivingBracketColumn::BudgetId.eq(budget_model.id))
+            .all(txn)
+            .await?;
+        let kept_bracket_ids: Vec<i32> = self
+            .budget
+            .brackets
+            .iter()
+            .map(|bracket| bracket.id)
+            .filter(|id| stored_brackets.iter().any(|stored| stored.id == *id))
+            .collect();
+
+        for stored in &stored_brackets {
+            if !kept_bracket_ids.contains(&stored.id) {
+                recorder.delete(EventEntityModel::GivingBracket, stored.id, stored)?;
+            }
+        }
+        GivingBracketEntity::delete_many()
+            .filter(GivingBracketColumn::BudgetId.eq(budget_model.id))
+            .filter(GivingBracketColumn::Id.is_not_in(kept_bracket_ids.clone()))
+            .exec(txn)
+            .await?;
+
+        for bracket in &self.budget.brackets {
+            let stored = stored_brackets
+                .iter()
+                .find(|stored| kept_bracket_ids.contains(&bracket.id) && stored.id == bracket.id);
+
+            if let Some(stored) = stored {
+                let desired = GivingBracketModel {
+                    budget_id: budget_model.id,
+                    ..bracket.into()
+                };
+                if desired != *stored {
+                    let updated = desired.into_active_model().reset_all().update(txn).await?;
+                    recorder.update(EventEntityModel::GivingBracket, updated.id, stored, &updated)?;
+                }
+            } else {
+                let inserted = ActiveGivingBracketModel {
+                    id: NotSet,
+                    budget_id: Set(budget_model.id),
+                    threshold: Set(bracket.threshold),
+                    percent: Set(bracket.percent),
+                }
+                .insert(txn)
+                .await?;
+                recorder.insert(EventEntityModel::GivingBracket, inserted.id, &inserted)?;
+            }
+        }
+
+        let stored_categories: Vec<CategoryModel> = CategoryEntity::find()
+            .filter(CategoryColumn::ListId.eq(self.id))
+            .all(txn)
+            .await?;
+        let kept_category_ids: Vec<i32> = self
+            .categories
+            .iter()
+            .map(|category| category.id)
+            .filter(|id| stored_categories.iter().any(|stored| stored.id == *id))
+            .collect();
+
+        let stored_entries: Vec<EntryModel> = EntryEntity::find()
+            .filter(
+                EntryColumn::CategoryId.is_in(
+                    stored_categories
+                        .iter()
+                        .map(|category| category.id)
+                        .collect::<Vec<i32>>(),
+                ),
+            )
+            .all(txn)
+            .await?;
+
+        // Entries are recorded before their category, so undo restores the category first.
+        let mut removed_category_ids = Vec::new();
+        for stored in &stored_categories {
+            if kept_category_ids.contains(&stored.id) {
+                continue;
+            }
+            for entry in stored_entries
+                .iter()
+                .filter(|entry| entry.category_id == stored.id)
+            {
+                recorder.delete(EventEntityModel::Entry, entry.id, entry)?;
+            }
+            recorder.delete(EventEntityModel::Category, stored.id, stored)?;
+            removed_category_ids.push(stored.id);
+        }
+        EntryEntity::delete_many()
+            .filter(EntryColumn::CategoryId.is_in(removed_category_ids))
+            .exec(txn)
+            .await?;
+        CategoryEntity::delete_many()
+            .filter(CategoryColumn::ListId.eq(self.id))
+            .filter(CategoryColumn::Id.is_not_in(kept_category_ids.clone()))
+            .exec(txn)
+            .await?;
+
+        // Names are unique per list, the index is checked row by row. Renamed categories
+        // first move to a placeholder no one can type, so names can be swapped or given to a
+        // new category in the same save.
+        for stored in &stored_categories {
+            let renamed = self.categories.iter().any(|category| {
+                category.id == stored.id
+                    && kept_category_ids.contains(&category.id)
+                    && category.name != stored.name
+            });
+            if renamed {
+                CategoryEntity::update_many()
+                    .col_expr(
+                        CategoryColumn::Name,
+                        Expr::value(format!("\u{1}{}", stored.id)),
+                    )
+                    .filter(CategoryColumn::Id.eq(stored.id))
+                    .exec(txn)
+                    .await?;
+            }
+        }
 
-    //     entry_models.
+        for category in &self.categories {
+            let stored = stored_categories.iter().find(|stored| {
+                kept_category_ids.contains(&category.id) && stored.id == category.id
+            });
 
-    // }
+            let category_id = if let Some(stored) = stored {
+                let desired = CategoryModel {
+                    list_id: self.id,
+                    version: stored.version,
+                    ..category.into()
+                };
+                if desired != *stored {
+                    let updated = CategoryModel { version, ..desired }
+                        .into_active_model()
+                        .reset_all()
+                        .update(txn)
+                        .await?;
+                    recorder.update(EventEntityModel::Category, updated.id, stored, &updated)?;
+                }
+                stored.id
+            } else {
+                let inserted = ActiveCategoryModel {
+                    id: NotSet,
+                    list_id: Set(self.id),
+                    name: Set(category.name.clone()),
+                    multiplier: Set(category.multiplier),
+                    percent_override: Set(category.percent_override),
+                    value_override: Set(category.value_override),
+                    enabled: Set(category.enabled),
+                    version: Set(version),
+                }
+                .insert(txn)
+                .await?;
+                recorder.insert(EventEntityModel::Category, inserted.id, &inserted)?;
+                inserted.id
+            };
+
+            let kept_entry_ids: Vec<i32> = category
+                .entries
+                .iter()
+                .map(|entry| entry.id)
+                .filter(|id| {
+                    stored_entries
+                        .iter()
+                        .any(|stored| stored.id == *id && stored.category_id == category_id)
+                })
+                .collect();
+
+            for stored in stored_entries.iter().filter(|stored| {
+                stored.category_id == category_id && !kept_entry_ids.contains(&stored.id)
+            }) {
+                recorder.delete(EventEntityModel::Entry, stored.id, stored)?;
+            }
+            EntryEntity::delete_many()
+                .filter(EntryColumn::CategoryId.eq(category_id))
+                .filter(EntryColumn::Id.is_not_in(kept_entry_ids.clone()))
+                .exec(txn)
+                .await?;
+
+            for entry in &category.entries {
+                let stored = stored_entries.iter().find(|stored| {
+                    kept_entry_ids.contains(&entry.id) && stored.id == entry.id
+                });
+
+                if let Some(stored) = stored {
+                    let desired = EntryModel {
+                        category_id,
+                        version: stored.version,
+                        ..entry.into()
+                    };
+                    if desired != *stored {
+                        let updated = EntryModel { version, ..desired }
+                            .into_active_model()
+                            .reset_all()
+                            .update(txn)
+                            .await?;
+                        recorder.update(EventEntityModel::Entry, updated.id, stored, &updated)?;
+                    }
+                } else {
+                    let inserted = ActiveEntryModel {
+                        id: NotSet,
+                        category_id: Set(category_id),
+                        ein: Set(entry.ein),
+                        multiplier: Set(entry.multiplier),
+                        percent_override: Set(entry.percent_override),
+                        value_override: Set(entry.value_override),
+                        enabled: Set(entry.enabled),
+                        version: Set(version),
+                    }
+                    .insert(txn)
+                    .await?;
+                    recorder.insert(EventEntityModel::Entry, inserted.id, &inserted)?;
+                }
+            }
+        }
+
+        recorder.write(txn, self.id, actor).await?;
+
+        List::from_id(txn, self.id)
+            .await
+            .ok_or(SaveError::Database(DbErr::RecordNotFound("Saved list.".to_owned())))
+    }
 }
 }}
@@ -0,0 +1,302 @@
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::allocation::{Allocation, CategoryAllocation};
+use super::{Category, List};
+
+/// A computed donation value and its share of the parent allocation.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Share {
+    pub donation_value: Decimal,
+    pub donation_percent: Decimal,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct EntryDiff {
+    pub ein: i32,
+
+    /// `None` when the entry is missing or receives nothing in that list.
+    pub live: Option<Share>,
+    pub scenario: Option<Share>,
+
+    pub value_change: Decimal,
+    pub percent_change: Decimal,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct CategoryDiff {
+    pub name: String,
+
+    /// `None` when the category is missing or not included in that list.
+    pub live: Option<Share>,
+    pub scenario: Option<Share>,
+
+    pub value_change: Decimal,
+    pub percent_change: Decimal,
+
+    pub entries: Vec<EntryDiff>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ScenarioDiff {
+    pub live_total: Decimal,
+    pub scenario_total: Decimal,
+
+    pub categories: Vec<CategoryDiff>,
+}
+
+impl ScenarioDiff {
+    pub fn has_changes(&self) -> bool {
+        self.live_total != self.scenario_total
+            || self.categories.iter().any(|category| {
+                category.live != category.scenario
+                    || category
+                        .entries
+                        .iter()
+                        .any(|entry| entry.live != entry.scenario)
+            })
+    }
+}
+
+fn find_category<'a>(
+    list: &'a List,
+    allocation: &'a Allocation,
+    name: &str,
+) -> Option<(&'a Category, &'a CategoryAllocation)> {
+    list.categories
+        .iter()
+        .enumerate()
+        .find(|(_, category)| category.name.eq_ignore_ascii_case(name))
+        .and_then(|(position, category)| {
+            allocation
+                .category(position, category)
+                .map(|allocated| (category, allocated))
+        })
+}
+
+fn category_allocation(category: Option<(&Category, &CategoryAllocation)>) -> Option<Share> {
+    category
+        .filter(|(_, allocated)| allocated.included)
+        .map(|(_, allocated)| Share {
+            donation_value: allocated.donation_value,
+            donation_percent: allocated.donation_percent,
+        })
+}
+
+fn entry_allocation(category: Option<(&Category, &CategoryAllocation)>, ein: i32) -> Option<Share> {
+    category
+        .filter(|(_, allocated)| allocated.included)
+        .and_then(|(category, allocated)| {
+            category
+                .entries
+                .iter()
+                .enumerate()
+                .find(|(_, entry)| entry.ein == ein)
+                .filter(|(_, entry)| entry.enabled)
+                .and_then(|(position, entry)| allocated.entry(position, entry))
+        })
+        .map(|allocated| Share {
+            donation_value: allocated.donation_value,
+            donation_percent: allocated.donation_percent,
+        })
+}
+
+fn change(live: Option<Share>, scenario: Option<Share>, field: fn(&Share) -> Decimal) -> Decimal {
+    scenario.as_ref().map_or(Decimal::ZERO, field) - live.as_ref().map_or(Decimal::ZERO, field)
+}
+
+impl List {
+    /// Processes the live list and `scenario` and compares the computed values. Categories
+    /// are matched by name, ignoring case, and entries by EIN.
+    pub fn compare_scenario(&self, scenario: &List) -> ScenarioDiff {
+        let live = self;
+        let live_allocation = live.process_list();
+        let scenario_allocation = scenario.process_list();
+
+        let mut names: Vec<&str> = Vec::new();
+        live.categories
+            .iter()
+            .chain(scenario.categories.iter())
+            .for_each(|category| {
+                if !names
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(&category.name))
+                {
+                    names.push(&category.name);
+                }
+            });
+
+        let categories = names
+            .into_iter()
+            .map(|name| {
+                let live_category = find_category(live, &live_allocation, name);
+                let scenario_category = find_category(scenario, &scenario_allocation, name);
+
+                let mut eins: Vec<i32> = live_category
+                    .into_iter()
+                    .chain(scenario_category)
+                    .flat_map(|(category, _)| category.entries.iter().map(|entry| entry.ein))
+                    .collect();
+                eins.sort_unstable();
+                eins.dedup();
+
+                let entries = eins
+                    .into_iter()
+                    .map(|ein| {
+                        let live = entry_allocation(live_category, ein);
+                        let scenario = entry_allocation(scenario_category, ein);
+
+                        EntryDiff {
+                            ein,
+                            live,
+                            scenario,
+                            value_change: change(live, scenario, |value| value.donation_value),
+                            percent_change: change(live, scenario, |value| value.donation_percent),
+                        }
+                    })
+                    .collect();
+
+                let live = category_allocation(live_category);
+                let scenario = category_allocation(scenario_category);
+
+                CategoryDiff {
+                    name: name.to_owned(),
+                    live,
+                    scenario,
+                    value_change: change(live, scenario, |value| value.donation_value),
+                    percent_change: change(live, scenario, |value| value.donation_percent),
+                    entries,
+                }
+            })
+            .collect();
+
+        ScenarioDiff {
+            live_total: live_allocation.budget.donation_value,
+            scenario_total: scenario_allocation.budget.donation_value,
+            categories,
+        }
+    }
+}
+
+use cfg_if::cfg_if;
+cfg_if! { if #[cfg(feature = "ssr")] {
+use sea_orm::*;
+
+use super::transfer::{ImportMode, ListExport};
+use super::SaveError;
+use crate::entity::budget::ActiveModel as ActiveBudgetModel;
+use crate::entity::list::{
+    ActiveModel as ActiveListModel, Column as ListColumn, Entity as ListEntity,
+};
+
+impl List {
+    /// Copies the list into a new scenario row. The copy gets fresh ids for its budget,
+    /// brackets, categories and entries, so saving it never touches the live list. The
+    /// scenario row and its contents are written in one transaction.
+    pub async fn fork_scenario(
+        &self,
+        db: &DatabaseConnection,
+        name: String,
+        actor: &str,
+    ) -> Result<List, SaveError> {
+        let live_id = self.scenario_of.unwrap_or(self.id);
+
+        let txn = db.begin().await?;
+
+        let scenario = ActiveListModel {
+            id: NotSet,
+            user_id: Set(self.user_id.clone()),
+            scenario_of: Set(Some(live_id)),
+            name: Set(Some(name)),
+            version: Set(1),
+        }
+        .insert(&txn)
+        .await?;
+
+        ActiveBudgetModel {
+            id: NotSet,
+            list_id: Set(scenario.id),
+            total_value: Set(self.budget.total_value),
+            donation_percent: Set(self.budget.donation_percent),
+            value_override: Set(self.budget.value_override),
+            mode: Set(self.budget.mode.into()),
+            income_basis: Set(self.budget.income_basis.into()),
+            net_value: Set(self.budget.net_value),
+            version: Set(1),
+        }
+        .insert(&txn)
+        .await?;
+
+        let mut copy = self.clone();
+        copy.id = scenario.id;
+        copy.scenario_of = scenario.scenario_of;
+        copy.name = scenario.name;
+        copy.version = scenario.version;
+        copy.budget.brackets.iter_mut().for_each(|bracket| bracket.id = 0);
+        copy.categories.iter_mut().for_each(|category| {
+            category.id = 0;
+            category.entries.iter_mut().for_each(|entry| entry.id = 0);
+        });
+
+        let scenario = copy.save_in(&txn, actor).await?;
+        txn.commit().await?;
+
+        Ok(scenario)
+    }
+
+    /// Scenarios forked from the live list `live_id`, oldest first.
+    pub async fn scenarios_of(db: &DatabaseConnection, live_id: i32) -> Result<Vec<List>, DbErr> {
+        let list_models = ListEntity::find()
+            .filter(ListColumn::ScenarioOf.eq(live_id))
+            .order_by_asc(ListColumn::Id)
+            .all(db)
+            .await?;
+
+        let mut scenarios = Vec::with_capacity(list_models.len());
+        for list_model in list_models {
+            let id = list_model.id;
+            scenarios.push(
+                List::from_id(db, id)
+                    .await
+                    .ok_or(DbErr::RecordNotFound(format!("Scenario {id}.")))?,
+            );
+        }
+
+        Ok(scenarios)
+    }
+
+    /// Replaces the live list with the contents of `scenario` and deletes the scenario.
+    /// Categories and entries keep their live ids when they match by name and EIN. The live
+    /// list is only changed if the scenario is deleted with it.
+    ///
+    /// Fails with `SaveError::Conflict` when the live list was saved since `version` was read.
+    pub async fn promote_scenario(
+        &self,
+        db: &DatabaseConnection,
+        scenario: &List,
+        version: i32,
+        actor: &str,
+    ) -> Result<List, SaveError> {
+        if scenario.scenario_of != Some(self.id) {
+            return Err(SaveError::Database(DbErr::Custom(format!(
+                "List {} is not a scenario of list {}.",
+                scenario.id, self.id
+            ))));
+        }
+
+        let txn = db.begin().await?;
+
+        let live = List {
+            version,
+            ..self.apply_import(&ListExport::from(scenario), ImportMode::Replace)
+        }
+        .save_in(&txn, actor)
+        .await?;
+        ListEntity::delete_by_id(scenario.id).exec(&txn).await?;
+
+        txn.commit().await?;
+
+        Ok(live)
+    }
+}
+}}
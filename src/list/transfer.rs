@@ -0,0 +1,1026 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{Budget, BudgetMode, Category, Entry, GivingBracket, IncomeBasis, List};
+
+/// Version of the JSON export schema. Bump when the shape of `ListExport` changes.
+pub const EXPORT_VERSION: u32 = 1;
+
+const CSV_HEADERS: [&str; 13] = [
+    "category",
+    "category_multiplier",
+    "category_percent_override",
+    "category_value_override",
+    "category_enabled",
+    "ein",
+    "multiplier",
+    "percent_override",
+    "value_override",
+    "enabled",
+    "donation_value",
+    "donation_percent",
+    "category_donation_value",
+];
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum TransferFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum ImportMode {
+    /// Imported categories become the whole list.
+    Replace,
+    /// Imported categories and entries are laid over the existing ones, matched by name and EIN.
+    Merge,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ListExport {
+    pub version: u32,
+    pub budget: Option<BudgetExport>,
+    pub categories: Vec<CategoryExport>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct BudgetExport {
+    pub total_value: Decimal,
+    pub donation_percent: Decimal,
+    pub value_override: Option<Decimal>,
+
+    #[serde(default)]
+    pub mode: BudgetMode,
+    #[serde(default)]
+    pub income_basis: IncomeBasis,
+    #[serde(default)]
+    pub net_value: Option<Decimal>,
+    #[serde(default)]
+    pub brackets: Vec<BracketExport>,
+
+    /// Computed, ignored on import.
+    #[serde(default)]
+    pub donation_value: Decimal,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct BracketExport {
+    pub threshold: Decimal,
+    pub percent: Decimal,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct CategoryExport {
+    pub name: String,
+
+    pub multiplier: Decimal,
+    pub percent_override: Option<Decimal>,
+    pub value_override: Option<Decimal>,
+
+    pub enabled: bool,
+
+    /// Computed, ignored on import.
+    #[serde(default)]
+    pub donation_value: Decimal,
+    /// Computed, ignored on import.
+    #[serde(default)]
+    pub donation_percent: Decimal,
+
+    pub entries: Vec<EntryExport>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct EntryExport {
+    pub ein: i32,
+
+    pub multiplier: Decimal,
+    pub percent_override: Option<Decimal>,
+    pub value_override: Option<Decimal>,
+
+    pub enabled: bool,
+
+    /// Computed, ignored on import.
+    #[serde(default)]
+    pub donation_value: Decimal,
+    /// Computed, ignored on import.
+    #[serde(default)]
+    pub donation_percent: Decimal,
+}
+
+/// A problem found while reading or validating an import. `location` points at the
+/// offending CSV line or JSON category/entry so it can be fixed in the source spreadsheet.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ImportIssue {
+    pub location: String,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+    Unchanged,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct EntryChange {
+    pub ein: i32,
+    pub kind: ChangeKind,
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct CategoryChange {
+    pub name: String,
+    pub kind: ChangeKind,
+    pub fields: Vec<FieldChange>,
+    pub entries: Vec<EntryChange>,
+}
+
+/// What an import would do to the user's list, shown before anything is written.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ImportPreview {
+    pub mode: ImportMode,
+    pub budget: Vec<FieldChange>,
+    pub categories: Vec<CategoryChange>,
+}
+
+impl ImportPreview {
+    pub fn has_changes(&self) -> bool {
+        !self.budget.is_empty()
+            || self
+                .categories
+                .iter()
+                .any(|category| category.kind != ChangeKind::Unchanged)
+    }
+}
+
+/// Includes the computed donations, the list is processed first.
+impl From<&List> for ListExport {
+    fn from(list: &List) -> Self {
+        let allocation = list.process_list();
+
+        Self {
+            version: EXPORT_VERSION,
+            budget: Some(BudgetExport {
+                total_value: list.budget.total_value,
+                donation_percent: list.budget.donation_percent,
+                value_override: list.budget.value_override,
+                mode: list.budget.mode,
+                income_basis: list.budget.income_basis,
+                net_value: list.budget.net_value,
+                brackets: list
+                    .budget
+                    .brackets
+                    .iter()
+                    .map(|bracket| BracketExport {
+                        threshold: bracket.threshold,
+                        percent: bracket.percent,
+                    })
+                    .collect(),
+                donation_value: allocation.budget.donation_value,
+            }),
+            categories: list
+                .categories
+                .iter()
+                .enumerate()
+                .map(|(position, category)| {
+                    let allocated = allocation.category(position, category);
+                    let entry_allocation = |position: usize, entry: &Entry| {
+                        allocated
+                            .and_then(|allocated| allocated.entry(position, entry).copied())
+                            .unwrap_or_default()
+                    };
+
+                    CategoryExport {
+                        name: category.name.clone(),
+                        multiplier: category.multiplier,
+                        percent_override: category.percent_override,
+                        value_override: category.value_override,
+                        enabled: category.enabled,
+                        donation_value: allocated
+                            .map_or(Decimal::ZERO, |allocated| allocated.donation_value),
+                        donation_percent: allocated
+                            .map_or(Decimal::ZERO, |allocated| allocated.donation_percent),
+                        entries: category
+                            .entries
+                            .iter()
+                            .enumerate()
+                            .map(|(position, entry)| {
+                                let allocated = entry_allocation(position, entry);
+
+                                EntryExport {
+                                    ein: entry.ein,
+                                    multiplier: entry.multiplier,
+                                    percent_override: entry.percent_override,
+                                    value_override: entry.value_override,
+                                    enabled: entry.enabled,
+                                    donation_value: allocated.donation_value,
+                                    donation_percent: allocated.donation_percent,
+                                }
+                            })
+                            .collect(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ListExport {
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// One row per entry. Categories without entries get a single row with an empty EIN
+    /// so they survive a round trip.
+    pub fn to_csv(&self) -> Result<String, csv::Error> {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.write_record(CSV_HEADERS)?;
+
+        for category in &self.categories {
+            let category_fields = [
+                category.name.clone(),
+                category.multiplier.to_string(),
+                optional_to_string(category.percent_override),
+                optional_to_string(category.value_override),
+                category.enabled.to_string(),
+            ];
+
+            if category.entries.is_empty() {
+                writer.write_record(category_fields.iter().cloned().chain([
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    category.donation_value.to_string(),
+                ]))?;
+                continue;
+            }
+
+            for entry in &category.entries {
+                writer.write_record(category_fields.iter().cloned().chain([
+                    entry.ein.to_string(),
+                    entry.multiplier.to_string(),
+                    optional_to_string(entry.percent_override),
+                    optional_to_string(entry.value_override),
+                    entry.enabled.to_string(),
+                    entry.donation_value.to_string(),
+                    entry.donation_percent.to_string(),
+                    category.donation_value.to_string(),
+                ]))?;
+            }
+        }
+
+        let bytes = writer
+            .into_inner()
+            .map_err(|error| csv::Error::from(error.into_error()))?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    pub fn from_json(data: &str) -> Result<Self, Vec<ImportIssue>> {
+        let export: ListExport =
+            serde_json::from_str(data).map_err(|error| vec![issue("json", error.to_string())])?;
+
+        if export.version != EXPORT_VERSION {
+            return Err(vec![issue(
+                "version",
+                format!(
+                    "Unsupported export version {}, expected {EXPORT_VERSION}.",
+                    export.version
+                ),
+            )]);
+        }
+
+        export.validate()?;
+        Ok(export)
+    }
+
+    /// Reads the CSV layout written by `to_csv`. Computed columns are optional and ignored.
+    /// The budget is not part of the CSV layout, so a CSV import never touches it.
+    pub fn from_csv(data: &str) -> Result<Self, Vec<ImportIssue>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(data.as_bytes());
+
+        let headers = reader
+            .headers()
+            .map_err(|error| vec![issue("header", error.to_string())])?
+            .clone();
+        let column = |name: &str| headers.iter().position(|header| header == name);
+
+        let mut issues = Vec::new();
+        let required = [
+            "category",
+            "category_multiplier",
+            "category_enabled",
+            "ein",
+            "multiplier",
+            "enabled",
+        ];
+        required
+            .iter()
+            .filter(|name| column(name).is_none())
+            .for_each(|name| issues.push(issue("header", format!("Missing column `{name}`."))));
+        if !issues.is_empty() {
+            return Err(issues);
+        }
+
+        let mut categories: Vec<CategoryExport> = Vec::new();
+
+        for record in reader.records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(error) => {
+                    issues.push(issue("csv", error.to_string()));
+                    continue;
+                }
+            };
+            let line = record
+                .position()
+                .map(|position| position.line())
+                .unwrap_or_default();
+            let location = format!("line {line}");
+            let field = |name: &str| {
+                column(name)
+                    .and_then(|index| record.get(index))
+                    .unwrap_or("")
+            };
+
+            let mut row_issues = Vec::new();
+            let category = CategoryExport {
+                name: field("category").to_owned(),
+                multiplier: parse_decimal(field("category_multiplier"), "category_multiplier")
+                    .unwrap_or_else(|message| {
+                        row_issues.push(message);
+                        Decimal::ONE
+                    }),
+                percent_override: parse_optional_decimal(
+                    field("category_percent_override"),
+                    "category_percent_override",
+                )
+                .unwrap_or_else(|message| {
+                    row_issues.push(message);
+                    None
+                }),
+                value_override: parse_optional_decimal(
+                    field("category_value_override"),
+                    "category_value_override",
+                )
+                .unwrap_or_else(|message| {
+                    row_issues.push(message);
+                    None
+                }),
+                enabled: parse_bool(field("category_enabled"), "category_enabled").unwrap_or_else(
+                    |message| {
+                        row_issues.push(message);
+                        true
+                    },
+                ),
+                donation_value: Decimal::ZERO,
+                donation_percent: Decimal::ZERO,
+                entries: Vec::new(),
+            };
+
+            let entry = if field("ein").is_empty() {
+                None
+            } else {
+                Some(EntryExport {
+                    ein: field("ein").parse::<i32>().unwrap_or_else(|_| {
+                        row_issues.push(format!("`ein` must be a number, got `{}`.", field("ein")));
+                        0
+                    }),
+                    multiplier: parse_decimal(field("multiplier"), "multiplier").unwrap_or_else(
+                        |message| {
+                            row_issues.push(message);
+                            Decimal::ONE
+                        },
+                    ),
+                    percent_override: parse_optional_decimal(
+                        field("percent_override"),
+                        "percent_override",
+                    )
+                    .unwrap_or_else(|message| {
+                        row_issues.push(message);
+                        None
+                    }),
+                    value_override: parse_optional_decimal(
+                        field("value_override"),
+                        "value_override",
+                    )
+                    .unwrap_or_else(|message| {
+                        row_issues.push(message);
+                        None
+                    }),
+                    enabled: parse_bool(field("enabled"), "enabled").unwrap_or_else(|message| {
+                        row_issues.push(message);
+                        true
+                    }),
+                    donation_value: Decimal::ZERO,
+                    donation_percent: Decimal::ZERO,
+                })
+            };
+
+            if !row_issues.is_empty() {
+                issues.extend(
+                    row_issues
+                        .into_iter()
+                        .map(|message| issue(&location, message)),
+                );
+                continue;
+            }
+
+            match categories
+                .iter_mut()
+                .find(|existing| existing.name.eq_ignore_ascii_case(&category.name))
+            {
+                Some(existing) => {
+                    if existing.multiplier != category.multiplier
+                        || existing.percent_override != category.percent_override
+                        || existing.value_override != category.value_override
+                        || existing.enabled != category.enabled
+                    {
+                        issues.push(issue(
+                            &location,
+                            format!(
+                                "Category `{}` has different settings than on an earlier row.",
+                                category.name
+                            ),
+                        ));
+                        continue;
+                    }
+                    existing.entries.extend(entry);
+                }
+                None => {
+                    let mut category = category;
+                    category.entries.extend(entry);
+                    categories.push(category);
+                }
+            }
+        }
+
+        if !issues.is_empty() {
+            return Err(issues);
+        }
+
+        let export = ListExport {
+            version: EXPORT_VERSION,
+            budget: None,
+            categories,
+        };
+        export.validate()?;
+        Ok(export)
+    }
+
+    pub fn parse(format: TransferFormat, data: &str) -> Result<Self, Vec<ImportIssue>> {
+        match format {
+            TransferFormat::Csv => ListExport::from_csv(data),
+            TransferFormat::Json => ListExport::from_json(data),
+        }
+    }
+
+    /// Checks the values a user could have typed into a spreadsheet. Returns every issue
+    /// found rather than stopping at the first one.
+    pub fn validate(&self) -> Result<(), Vec<ImportIssue>> {
+        let mut issues = Vec::new();
+
+        if let Some(budget) = &self.budget {
+            if budget.total_value < Decimal::ZERO {
+                issues.push(issue("budget", "`total_value` must not be negative."));
+            }
+            if !is_fraction(budget.donation_percent) {
+                issues.push(issue(
+                    "budget",
+                    "`donation_percent` must be between 0 and 1.",
+                ));
+            }
+            if budget
+                .value_override
+                .is_some_and(|value| value < Decimal::ZERO)
+            {
+                issues.push(issue("budget", "`value_override` must not be negative."));
+            }
+            if budget.net_value.is_some_and(|value| value < Decimal::ZERO) {
+                issues.push(issue("budget", "`net_value` must not be negative."));
+            }
+
+            let mut thresholds = HashSet::new();
+            for bracket in &budget.brackets {
+                let location = format!("budget bracket at {}", bracket.threshold);
+
+                if bracket.threshold < Decimal::ZERO {
+                    issues.push(issue(&location, "`threshold` must not be negative."));
+                }
+                if !thresholds.insert(bracket.threshold) {
+                    issues.push(issue(
+                        &location,
+                        "Bracket threshold is used more than once.",
+                    ));
+                }
+                if !is_fraction(bracket.percent) {
+                    issues.push(issue(&location, "`percent` must be between 0 and 1."));
+                }
+            }
+        }
+
+        let mut names = HashSet::new();
+        for category in &self.categories {
+            let location = format!("category `{}`", category.name);
+
+            if category.name.trim().is_empty() {
+                issues.push(issue(&location, "Category name must not be empty."));
+            }
+            if !names.insert(category.name.to_lowercase()) {
+                issues.push(issue(&location, "Category name is used more than once."));
+            }
+            validate_weights(
+                &location,
+                category.multiplier,
+                category.percent_override,
+                category.value_override,
+                &mut issues,
+            );
+
+            let mut eins = HashSet::new();
+            for entry in &category.entries {
+                let location = format!("{location}, EIN {}", entry.ein);
+
+                if !(1..=999_999_999).contains(&entry.ein) {
+                    issues.push(issue(&location, "EIN must be a positive 9 digit number."));
+                }
+                if !eins.insert(entry.ein) {
+                    issues.push(issue(
+                        &location,
+                        "EIN is listed more than once in the category.",
+                    ));
+                }
+                validate_weights(
+                    &location,
+                    entry.multiplier,
+                    entry.percent_override,
+                    entry.value_override,
+                    &mut issues,
+                );
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+}
+
+impl List {
+    /// Runs the checks an import goes through, and rejects value overrides larger than the
+    /// budget's donation.
+    pub fn validate(&self) -> Result<(), Vec<ImportIssue>> {
+        let mut issues = ListExport::from(self).validate().err().unwrap_or_default();
+        let budget_value = self.process_list().budget.donation_value;
+
+        for category in &self.categories {
+            let location = format!("category `{}`", category.name);
+
+            if category
+                .value_override
+                .is_some_and(|value| value > budget_value)
+            {
+                issues.push(issue(
+                    &location,
+                    "`value_override` must not be larger than the budget.",
+                ));
+            }
+            for entry in &category.entries {
+                if entry
+                    .value_override
+                    .is_some_and(|value| value > budget_value)
+                {
+                    issues.push(issue(
+                        &format!("{location}, EIN {}", entry.ein),
+                        "`value_override` must not be larger than the budget.",
+                    ));
+                }
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// Builds the list that importing `import` would produce. Existing categories and entries
+    /// keep their ids when they match by name and EIN, new ones get id 0 so `save` inserts them.
+    pub fn apply_import(&self, import: &ListExport, mode: ImportMode) -> List {
+        let budget = match &import.budget {
+            Some(budget) => Budget {
+                total_value: budget.total_value,
+                donation_percent: budget.donation_percent,
+                value_override: budget.value_override,
+                mode: budget.mode,
+                income_basis: budget.income_basis,
+                net_value: budget.net_value,
+                brackets: budget
+                    .brackets
+                    .iter()
+                    .map(|bracket| GivingBracket {
+                        id: self
+                            .budget
+                            .brackets
+                            .iter()
+                            .find(|existing| existing.threshold == bracket.threshold)
+                            .map_or(0, |existing| existing.id),
+                        budget_id: self.budget.id,
+                        threshold: bracket.threshold,
+                        percent: bracket.percent,
+                    })
+                    .collect(),
+                ..self.budget.clone()
+            },
+            None => self.budget.clone(),
+        };
+
+        let mut categories: Vec<Category> = match mode {
+            ImportMode::Replace => Vec::new(),
+            ImportMode::Merge => self.categories.clone(),
+        };
+
+        for imported in &import.categories {
+            let existing = self
+                .categories
+                .iter()
+                .find(|category| category.name.eq_ignore_ascii_case(&imported.name));
+
+            let entries: Vec<Entry> = imported
+                .entries
+                .iter()
+                .map(|entry| {
+                    let existing_entry = existing.and_then(|category| {
+                        category
+                            .entries
+                            .iter()
+                            .find(|existing_entry| existing_entry.ein == entry.ein)
+                    });
+
+                    Entry {
+                        id: existing_entry.map_or(0, |existing_entry| existing_entry.id),
+                        category_id: existing.map_or(0, |category| category.id),
+                        ein: entry.ein,
+                        multiplier: entry.multiplier,
+                        percent_override: entry.percent_override,
+                        value_override: entry.value_override,
+                        enabled: entry.enabled,
+                        version: existing_entry.map_or(0, |existing_entry| existing_entry.version),
+                    }
+                })
+                .collect();
+
+            let mut category = Category {
+                id: existing.map_or(0, |category| category.id),
+                list_id: self.id,
+                name: imported.name.clone(),
+                entries: Vec::new(),
+                multiplier: imported.multiplier,
+                percent_override: imported.percent_override,
+                value_override: imported.value_override,
+                enabled: imported.enabled,
+                version: existing.map_or(0, |category| category.version),
+            };
+
+            match categories
+                .iter_mut()
+                .find(|merged| merged.name.eq_ignore_ascii_case(&imported.name))
+            {
+                Some(merged) => {
+                    let mut merged_entries = std::mem::take(&mut merged.entries);
+                    for entry in entries {
+                        match merged_entries
+                            .iter_mut()
+                            .find(|merged_entry| merged_entry.ein == entry.ein)
+                        {
+                            Some(merged_entry) => *merged_entry = entry,
+                            None => merged_entries.push(entry),
+                        }
+                    }
+                    category.entries = merged_entries;
+                    *merged = category;
+                }
+                None => {
+                    category.entries = entries;
+                    categories.push(category);
+                }
+            }
+        }
+
+        List {
+            id: self.id,
+            user_id: self.user_id.clone(),
+            scenario_of: self.scenario_of,
+            name: self.name.clone(),
+            version: self.version,
+            budget,
+            categories,
+        }
+    }
+
+    /// Compares the stored fields of two lists, matching categories by name and entries by EIN.
+    pub fn preview_changes(&self, result: &List, mode: ImportMode) -> ImportPreview {
+        let mut budget = Vec::new();
+        compare_field(
+            &mut budget,
+            "total_value",
+            self.budget.total_value,
+            result.budget.total_value,
+        );
+        compare_field(
+            &mut budget,
+            "donation_percent",
+            self.budget.donation_percent,
+            result.budget.donation_percent,
+        );
+        compare_optional_field(
+            &mut budget,
+            "value_override",
+            self.budget.value_override,
+            result.budget.value_override,
+        );
+        compare_field(
+            &mut budget,
+            "mode",
+            format!("{:?}", self.budget.mode),
+            format!("{:?}", result.budget.mode),
+        );
+        compare_field(
+            &mut budget,
+            "income_basis",
+            format!("{:?}", self.budget.income_basis),
+            format!("{:?}", result.budget.income_basis),
+        );
+        compare_optional_field(
+            &mut budget,
+            "net_value",
+            self.budget.net_value,
+            result.budget.net_value,
+        );
+        compare_field(
+            &mut budget,
+            "brackets",
+            format_brackets(&self.budget.brackets),
+            format_brackets(&result.budget.brackets),
+        );
+
+        let mut categories: Vec<CategoryChange> = result
+            .categories
+            .iter()
+            .map(|after| {
+                let before = self
+                    .categories
+                    .iter()
+                    .find(|before| before.name.eq_ignore_ascii_case(&after.name));
+                category_change(before, Some(after))
+            })
+            .collect();
+
+        categories.extend(
+            self.categories
+                .iter()
+                .filter(|before| {
+                    !result
+                        .categories
+                        .iter()
+                        .any(|after| after.name.eq_ignore_ascii_case(&before.name))
+                })
+                .map(|before| category_change(Some(before), None)),
+        );
+
+        ImportPreview {
+            mode,
+            budget,
+            categories,
+        }
+    }
+}
+
+fn category_change(before: Option<&Category>, after: Option<&Category>) -> CategoryChange {
+    let (name, kind) = match (before, after) {
+        (None, Some(after)) => (after.name.clone(), ChangeKind::Added),
+        (Some(before), None) => (before.name.clone(), ChangeKind::Removed),
+        (_, Some(after)) => (after.name.clone(), ChangeKind::Unchanged),
+        (None, None) => unreachable!("a change needs at least one side"),
+    };
+
+    let mut fields = Vec::new();
+    if let (Some(before), Some(after)) = (before, after) {
+        compare_field(
+            &mut fields,
+            "multiplier",
+            before.multiplier,
+            after.multiplier,
+        );
+        compare_optional_field(
+            &mut fields,
+            "percent_override",
+            before.percent_override,
+            after.percent_override,
+        );
+        compare_optional_field(
+            &mut fields,
+            "value_override",
+            before.value_override,
+            after.value_override,
+        );
+        compare_field(&mut fields, "enabled", before.enabled, after.enabled);
+    }
+
+    let before_entries = before.map_or(&[][..], |category| &category.entries[..]);
+    let after_entries = after.map_or(&[][..], |category| &category.entries[..]);
+
+    let mut entries: Vec<EntryChange> = after_entries
+        .iter()
+        .map(|after| {
+            let before = before_entries.iter().find(|before| before.ein == after.ein);
+            entry_change(before, Some(after))
+        })
+        .collect();
+    entries.extend(
+        before_entries
+            .iter()
+            .filter(|before| !after_entries.iter().any(|after| after.ein == before.ein))
+            .map(|before| entry_change(Some(before), None)),
+    );
+
+    let kind = match kind {
+        ChangeKind::Unchanged
+            if !fields.is_empty()
+                || entries
+                    .iter()
+                    .any(|entry| entry.kind != ChangeKind::Unchanged) =>
+        {
+            ChangeKind::Modified
+        }
+        kind => kind,
+    };
+
+    CategoryChange {
+        name,
+        kind,
+        fields,
+        entries,
+    }
+}
+
+fn entry_change(before: Option<&Entry>, after: Option<&Entry>) -> EntryChange {
+    match (before, after) {
+        (Some(before), Some(after)) => {
+            let mut fields = Vec::new();
+            compare_field(
+                &mut fields,
+                "multiplier",
+                before.multiplier,
+                after.multiplier,
+            );
+            compare_optional_field(
+                &mut fields,
+                "percent_override",
+                before.percent_override,
+                after.percent_override,
+            );
+            compare_optional_field(
+                &mut fields,
+                "value_override",
+                before.value_override,
+                after.value_override,
+            );
+            compare_field(&mut fields, "enabled", before.enabled, after.enabled);
+
+            EntryChange {
+                ein: after.ein,
+                kind: if fields.is_empty() {
+                    ChangeKind::Unchanged
+                } else {
+                    ChangeKind::Modified
+                },
+                fields,
+            }
+        }
+        (None, Some(after)) => EntryChange {
+            ein: after.ein,
+            kind: ChangeKind::Added,
+            fields: Vec::new(),
+        },
+        (Some(before), None) => EntryChange {
+            ein: before.ein,
+            kind: ChangeKind::Removed,
+            fields: Vec::new(),
+        },
+        (None, None) => unreachable!("a change needs at least one side"),
+    }
+}
+
+fn compare_field<T: PartialEq + ToString>(
+    fields: &mut Vec<FieldChange>,
+    field: &str,
+    before: T,
+    after: T,
+) {
+    if before != after {
+        fields.push(FieldChange {
+            field: field.to_owned(),
+            before: before.to_string(),
+            after: after.to_string(),
+        });
+    }
+}
+
+fn compare_optional_field(
+    fields: &mut Vec<FieldChange>,
+    field: &str,
+    before: Option<Decimal>,
+    after: Option<Decimal>,
+) {
+    compare_field(
+        fields,
+        field,
+        optional_to_string(before),
+        optional_to_string(after),
+    );
+}
+
+fn validate_weights(
+    location: &str,
+    multiplier: Decimal,
+    percent_override: Option<Decimal>,
+    value_override: Option<Decimal>,
+    issues: &mut Vec<ImportIssue>,
+) {
+    if multiplier < Decimal::ZERO {
+        issues.push(issue(location, "`multiplier` must not be negative."));
+    }
+    if percent_override.is_some_and(|percent| !is_fraction(percent)) {
+        issues.push(issue(
+            location,
+            "`percent_override` must be between 0 and 1.",
+        ));
+    }
+    if value_override.is_some_and(|value| value < Decimal::ZERO) {
+        issues.push(issue(location, "`value_override` must not be negative."));
+    }
+}
+
+fn format_brackets(brackets: &[GivingBracket]) -> String {
+    brackets
+        .iter()
+        .map(|bracket| format!("{}@{}", bracket.threshold, bracket.percent))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn is_fraction(value: Decimal) -> bool {
+    (Decimal::ZERO..=Decimal::ONE).contains(&value)
+}
+
+fn issue(location: &str, message: impl Into<String>) -> ImportIssue {
+    ImportIssue {
+        location: location.to_owned(),
+        message: message.into(),
+    }
+}
+
+fn optional_to_string(value: Option<Decimal>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn parse_decimal(value: &str, column: &str) -> Result<Decimal, String> {
+    Decimal::from_str(value).map_err(|_| format!("`{column}` must be a number, got `{value}`."))
+}
+
+fn parse_optional_decimal(value: &str, column: &str) -> Result<Option<Decimal>, String> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    parse_decimal(value, column).map(Some)
+}
+
+fn parse_bool(value: &str, column: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(format!("`{column}` must be true or false, got `{value}`.")),
+    }
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+use crate::list::List;
+
+/// Sent to every subscriber of a list after a save, undo or redo changed it.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ListUpdate {
+    /// Clerk user id of whoever made the change.
+    pub actor: String,
+    pub list: List,
+}
+
+impl List {
+    /// Applies an update from another client. Local edits that have not been saved yet are
+    /// kept unless the update changed the same rows, older updates are ignored.
+    pub fn apply_update(&mut self, update: &ListUpdate) {
+        if update.list.id != self.id || update.list.version <= self.version {
+            return;
+        }
+
+        *self = self.merge_onto(&update.list);
+    }
+}
+
+use cfg_if::cfg_if;
+cfg_if! { if #[cfg(feature = "ssr")] {
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use actix_web::{
+    get,
+    http::header::{CacheControl, CacheDirective},
+    web::{Bytes, Data, Path},
+    HttpResponse,
+};
+use sea_orm::DatabaseConnection;
+use tokio::sync::broadcast::{self, error::RecvError, Receiver, Sender};
+
+use crate::clerk_middleware::current_user::CurrentUser;
+
+/// How many updates a slow subscriber may fall behind before it skips to the newest.
+const CHANNEL_CAPACITY: usize = 16;
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Broadcasts list updates to the subscribers of each list. Shared between workers, so
+/// `main` builds it once and hands out clones of the same `Data`.
+#[derive(Default)]
+pub struct ListHub {
+    channels: Mutex<HashMap<i32, Sender<ListUpdate>>>,
+}
+
+impl ListHub {
+    pub fn subscribe(&self, list_id: i32) -> Receiver<ListUpdate> {
+        self.channels
+            .lock()
+            .expect("List hub lock poisoned.")
+            .entry(list_id)
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    pub fn publish(&self, list: &List, actor: &str) {
+        let mut channels = self.channels.lock().expect("List hub lock poisoned.");
+
+        if let Some(sender) = channels.get(&list.id) {
+            let update = ListUpdate {
+                actor: actor.to_owned(),
+                list: list.clone(),
+            };
+            // Sending only fails without receivers, then the channel is no longer needed.
+            if sender.send(update).is_err() {
+                channels.remove(&list.id);
+            }
+        }
+    }
+}
+
+/// Server-sent events stream of `ListUpdate`s for one of the user's lists.
+#[get("/{list_id}")]
+pub async fn list_events(
+    db: Data<DatabaseConnection>,
+    hub: Data<ListHub>,
+    list_id: Path<i32>,
+    user: CurrentUser,
+) -> actix_web::Result<HttpResponse> {
+    let list = List::from_id(db.get_ref(), list_id.into_inner())
+        .await
+        .filter(|list| list.user_id == user.id)
+        .ok_or_else(|| actix_web::error::ErrorNotFound("No list found for user."))?;
+
+    let receiver = hub.subscribe(list.id);
+    let events = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            let event = match tokio::time::timeout(KEEP_ALIVE_INTERVAL, receiver.recv()).await {
+                Err(_) => Bytes::from_static(b": keep-alive\n\n"),
+                Ok(Ok(update)) => match serde_json::to_string(&update) {
+                    Ok(data) => Bytes::from(format!("event: list\ndata: {data}\n\n")),
+                    Err(_) => continue,
+                },
+                Ok(Err(RecvError::Lagged(_))) => continue,
+                Ok(Err(RecvError::Closed)) => return None,
+            };
+            return Some((Ok::<_, actix_web::Error>(event), receiver));
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        .streaming(events))
+}
+}}
@@ -1,22 +1,63 @@
-pub mod clerk_middleware;
-pub mod database;
-pub mod entity;
-pub mod migration;
-
 #[cfg(feature = "ssr")]
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     use actix_files::Files;
     use actix_web::*;
-    use clerk_middleware::ClerkMiddleware;
-    use clerk_rs::ClerkConfiguration;
-    use database::*;
     use leptos::*;
     use leptos_actix::{generate_route_list, LeptosRoutes};
     use leptos_start::app::*;
-    use std::env;
+    use leptos_start::clerk_middleware::{
+        jwks::{JwksCache, JwksCacheConfig},
+        provider::{AuthProvider, ClerkProvider, LocalProvider},
+        routes::RouteMatcher,
+        ClerkMiddleware, JwtValidation,
+    };
+    use leptos_start::config::{AppConfig, AuthConfig};
+    use leptos_start::database::init_database;
+    use leptos_start::health::healthz;
+    use leptos_start::live::{list_events, ListHub};
+    use leptos_start::report::{report_html, report_pdf};
+    use std::sync::Arc;
+
+    let config = AppConfig::load().unwrap_or_else(|errors| {
+        eprintln!("{errors}");
+        std::process::exit(1);
+    });
 
-    let db = init_database().await;
+    let db = init_database(&config.database).await;
+    // Built once so every worker publishes to the same subscribers.
+    let hub = web::Data::new(ListHub::default());
+
+    // `AUTH_PROVIDER=local` signs users in without Clerk, for offline development and tests.
+    let (provider, local): (Arc<dyn AuthProvider>, _) = match &config.auth {
+        AuthConfig::Local(key) => {
+            let local =
+                Arc::new(LocalProvider::from_key(key).expect("Unable to load the local auth key."));
+            (local.clone(), Some(local))
+        }
+        AuthConfig::Clerk {
+            secret_key,
+            issuer,
+            authorized_parties,
+        } => {
+            // Shared by every worker so the key set is fetched once, not per request.
+            let jwks = JwksCache::new(JwksCacheConfig::clerk(secret_key.clone()));
+            let validation = JwtValidation {
+                issuer: issuer.clone(),
+                authorized_parties: authorized_parties.clone(),
+                ..Default::default()
+            };
+            (Arc::new(ClerkProvider { jwks, validation }), None)
+        }
+    };
+    let clerk = ClerkMiddleware::new(
+        provider.clone(),
+        RouteMatcher::default()
+            .public("/api/get_auth_provider*")
+            .public("/api/is_logged_in*")
+            .public("/api/local_sign_in*")
+            .public("/api/local_sign_out*"),
+    );
 
     let conf = get_configuration(None).await.unwrap();
     let addr = conf.leptos_options.site_addr;
@@ -27,29 +68,42 @@ async fn main() -> std::io::Result<()> {
         let leptos_options = &conf.leptos_options;
         let site_root = &leptos_options.site_root;
 
-        dotenvy::dotenv().expect("Unable to read .env file.");
-        let clerk_secret_key =
-            env::var("CLERK_SECRET_KEY").expect("Unable to load CLERK_SECRET_KEY from env.");
-        let clerk_config = ClerkConfiguration::new(None, None, Some(clerk_secret_key), None);
-
         App::new()
             .app_data(web::Data::new(db.clone()))
+            .app_data(web::Data::new(config.clone()))
+            .app_data(hub.clone())
+            .app_data(web::Data::from(provider.clone()))
+            .configure(|cfg| {
+                if let Some(local) = &local {
+                    cfg.app_data(web::Data::from(local.clone()));
+                }
+            })
+            .service(
+                web::scope("/report")
+                    .service(report_html)
+                    .service(report_pdf)
+                    .wrap(clerk.clone()),
+            )
+            .service(web::scope("/live").service(list_events).wrap(clerk.clone()))
             .service(
                 web::resource("/api/{tail:.*}")
                     .route(leptos_actix::handle_server_fns())
-                    .wrap(ClerkMiddleware::new(clerk_config, None)),
+                    .wrap(clerk.clone()),
             )
             // .route("/api/{tail:.*}", leptos_actix::handle_server_fns())
             .service(Files::new("/pkg", format!("{site_root}/pkg")))
             .service(Files::new("/assets", site_root))
             .service(favicon)
-            // .wrap(ClerkMiddleware::new(clerk_config, None))
+            .service(healthz)
+            // .wrap(clerk.clone())
             .leptos_routes(
                 leptos_options.to_owned(),
                 routes.to_owned(),
                 |cx| view! { cx, <App/> },
             )
             .app_data(web::Data::new(leptos_options.to_owned()))
+            // Outside of `ClerkMiddleware` so preflights and auth errors get CORS headers
+            .wrap(cors(&config.cors_allowed_origins))
         //.wrap(middleware::Compress::default())
     })
     .bind(&addr)?
@@ -57,6 +111,21 @@ async fn main() -> std::io::Result<()> {
     .await
 }
 
+/// Lets the listed origins call the API with either the session cookie or a bearer token.
+#[cfg(feature = "ssr")]
+fn cors(allowed_origins: &[String]) -> actix_cors::Cors {
+    use actix_web::http::header;
+
+    allowed_origins.iter().fold(
+        actix_cors::Cors::default()
+            .allowed_methods(["GET", "POST"])
+            .allowed_headers([header::AUTHORIZATION, header::CONTENT_TYPE, header::ACCEPT])
+            .supports_credentials()
+            .max_age(3600),
+        |cors, origin| cors.allowed_origin(origin),
+    )
+}
+
 #[cfg(feature = "ssr")]
 #[actix_web::get("favicon.ico")]
 async fn favicon(
@@ -84,7 +153,6 @@ pub fn main() {
     // to run: `trunk serve --open --features csr`
     use leptos::*;
     use leptos_start::app::*;
-    use wasm_bindgen::prelude::wasm_bindgen;
 
     console_error_panic_hook::set_once();
 
@@ -1,9 +1,5 @@
-use futures::future::join_all;
-use sea_orm::{ActiveModelTrait, Set};
 use sea_orm_migration::prelude::*;
 
-use crate::entity::default_category;
-
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
@@ -17,6 +13,7 @@ pub enum DefaultCategory {
 #[async_trait::async_trait]
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Rows are seeded by `database::seed` once every migration has run.
         manager
             .create_table(
                 Table::create()
@@ -32,37 +29,7 @@ impl MigrationTrait for Migration {
                     .col(ColumnDef::new(DefaultCategory::Name).string().not_null())
                     .to_owned(),
             )
-            .await?;
-
-        let db = manager.get_connection();
-
-        let default_categories = vec![
-            "Animal Welfare",
-            "Culture",
-            "Education",
-            "Environment",
-            "Global Health",
-            "Health Care",
-            "Humanitarian Aid",
-            "Justice",
-            "Local Causes",
-            "Scientific Research",
-        ];
-
-        let default_categories_setup = default_categories.iter().map(|name| {
-            default_category::ActiveModel {
-                name: Set(name.to_string()),
-                ..Default::default()
-            }
-            .insert(db)
-        });
-
-        let result = join_all(default_categories_setup)
             .await
-            .into_iter()
-            .collect::<Result<Vec<default_category::Model>, DbErr>>();
-
-        result.map(|_| ())
     }
 
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Nonprofit {
+    Table,
+    Ein,
+    Name,
+    DeductibilityCode,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Nonprofit::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Nonprofit::Ein)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Nonprofit::Name).string().not_null())
+                    .col(ColumnDef::new(Nonprofit::DeductibilityCode).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Nonprofit::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000003_create_list_table::List;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Donation {
+    Table,
+    Id,
+    ListId,
+    Ein,
+    Amount,
+    DonatedOn,
+    ReceiptReference,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Donation::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Donation::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Donation::ListId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Donation::Table, Donation::ListId)
+                            .to(List::Table, List::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Donation::Ein).integer().not_null())
+                    .col(ColumnDef::new(Donation::Amount).decimal().not_null())
+                    .col(ColumnDef::new(Donation::DonatedOn).date().not_null())
+                    .col(ColumnDef::new(Donation::ReceiptReference).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Donation::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,112 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Budget {
+    Table,
+    Id,
+    Mode,
+    IncomeBasis,
+    NetValue,
+}
+
+#[derive(DeriveIden)]
+pub enum GivingBracket {
+    Table,
+    Id,
+    BudgetId,
+    Threshold,
+    Percent,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One column per statement, SQLite cannot alter several at once.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Budget::Table)
+                    .add_column(
+                        ColumnDef::new(Budget::Mode)
+                            .string_len(16)
+                            .not_null()
+                            .default("percent"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Budget::Table)
+                    .add_column(
+                        ColumnDef::new(Budget::IncomeBasis)
+                            .string_len(16)
+                            .not_null()
+                            .default("gross"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Budget::Table)
+                    .add_column(ColumnDef::new(Budget::NetValue).decimal().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(GivingBracket::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GivingBracket::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(GivingBracket::BudgetId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(GivingBracket::Table, GivingBracket::BudgetId)
+                            .to(Budget::Table, Budget::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(GivingBracket::Threshold)
+                            .decimal()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(GivingBracket::Percent).decimal().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GivingBracket::Table).to_owned())
+            .await?;
+
+        for column in [Budget::NetValue, Budget::IncomeBasis, Budget::Mode] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Budget::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
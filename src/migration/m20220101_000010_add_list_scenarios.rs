@@ -0,0 +1,91 @@
+use sea_orm::DbBackend;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum List {
+    Table,
+    Id,
+    ScenarioOf,
+    Name,
+}
+
+const SCENARIO_OF_FOREIGN_KEY: &str = "fk-list-scenario_of";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Scenarios are deleted together with the live list they were forked from. SQLite
+        // cannot add a foreign key to an existing table, only a column that references one.
+        let mut scenario_of = ColumnDef::new(List::ScenarioOf);
+        scenario_of.integer().null();
+        if manager.get_database_backend() == DbBackend::Sqlite {
+            scenario_of.extra(r#"REFERENCES "list" ("id") ON DELETE CASCADE ON UPDATE CASCADE"#);
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(List::Table)
+                    .add_column(&mut scenario_of)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(List::Table)
+                    .add_column(ColumnDef::new(List::Name).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        if manager.get_database_backend() != DbBackend::Sqlite {
+            manager
+                .create_foreign_key(
+                    ForeignKey::create()
+                        .name(SCENARIO_OF_FOREIGN_KEY)
+                        .from(List::Table, List::ScenarioOf)
+                        .to(List::Table, List::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                        .on_update(ForeignKeyAction::Cascade)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // On SQLite the reference goes with the column.
+        if manager.get_database_backend() != DbBackend::Sqlite {
+            manager
+                .drop_foreign_key(
+                    ForeignKey::drop()
+                        .name(SCENARIO_OF_FOREIGN_KEY)
+                        .table(List::Table)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(List::Table)
+                    .drop_column(List::Name)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(List::Table)
+                    .drop_column(List::ScenarioOf)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,95 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000003_create_list_table::List;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum ListEvent {
+    Table,
+    Id,
+    ListId,
+    ChangeSet,
+    Actor,
+    CreatedAt,
+    Entity,
+    EntityId,
+    Action,
+    Before,
+    After,
+    State,
+    Origin,
+    OriginChangeSet,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ListEvent::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ListEvent::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ListEvent::ListId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ListEvent::Table, ListEvent::ListId)
+                            .to(List::Table, List::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(ListEvent::ChangeSet).integer().not_null())
+                    .col(ColumnDef::new(ListEvent::Actor).string().not_null())
+                    .col(
+                        ColumnDef::new(ListEvent::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ListEvent::Entity).string_len(16).not_null())
+                    .col(ColumnDef::new(ListEvent::EntityId).integer().not_null())
+                    .col(ColumnDef::new(ListEvent::Action).string_len(16).not_null())
+                    .col(ColumnDef::new(ListEvent::Before).json().null())
+                    .col(ColumnDef::new(ListEvent::After).json().null())
+                    .col(
+                        ColumnDef::new(ListEvent::State)
+                            .string_len(16)
+                            .not_null()
+                            .default("applied"),
+                    )
+                    .col(
+                        ColumnDef::new(ListEvent::Origin)
+                            .string_len(16)
+                            .not_null()
+                            .default("save"),
+                    )
+                    .col(ColumnDef::new(ListEvent::OriginChangeSet).integer().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-list_event-list_id-change_set")
+                    .table(ListEvent::Table)
+                    .col(ListEvent::ListId)
+                    .col(ListEvent::ChangeSet)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ListEvent::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum List {
+    Table,
+    Version,
+}
+
+#[derive(DeriveIden)]
+pub enum Budget {
+    Table,
+    Version,
+}
+
+#[derive(DeriveIden)]
+pub enum Category {
+    Table,
+    Version,
+}
+
+#[derive(DeriveIden)]
+pub enum Entry {
+    Table,
+    Version,
+}
+
+fn versioned_tables() -> [(DynIden, DynIden); 4] {
+    [
+        (List::Table.into_iden(), List::Version.into_iden()),
+        (Budget::Table.into_iden(), Budget::Version.into_iden()),
+        (Category::Table.into_iden(), Category::Version.into_iden()),
+        (Entry::Table.into_iden(), Entry::Version.into_iden()),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (table, version) in versioned_tables() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .add_column(ColumnDef::new(version).integer().not_null().default(1))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (table, version) in versioned_tables() {
+            manager
+                .alter_table(Table::alter().table(table).drop_column(version).to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum List {
+    Table,
+    UserId,
+    ScenarioOf,
+}
+
+#[derive(DeriveIden)]
+pub enum Budget {
+    Table,
+    ListId,
+}
+
+#[derive(DeriveIden)]
+pub enum GivingBracket {
+    Table,
+    BudgetId,
+}
+
+#[derive(DeriveIden)]
+pub enum Category {
+    Table,
+    ListId,
+    Name,
+}
+
+#[derive(DeriveIden)]
+pub enum Entry {
+    Table,
+    CategoryId,
+}
+
+#[derive(DeriveIden)]
+pub enum Donation {
+    Table,
+    ListId,
+}
+
+/// Keeps the oldest budget of each list, the others and their brackets are dropped.
+const DEDUPLICATE_BUDGETS: &str = r#"
+DELETE FROM budget
+WHERE id NOT IN (SELECT MIN(id) FROM budget GROUP BY list_id)
+"#;
+
+/// Moves the entries of a list's categories that share a name into the oldest of them.
+const MERGE_DUPLICATE_CATEGORIES: &str = r#"
+UPDATE entry SET category_id = (
+    SELECT MIN(keep.id)
+    FROM category AS duplicate
+    JOIN category AS keep ON keep.list_id = duplicate.list_id AND keep.name = duplicate.name
+    WHERE duplicate.id = entry.category_id
+)
+WHERE category_id NOT IN (SELECT MIN(id) FROM category GROUP BY list_id, name)
+"#;
+
+const DEDUPLICATE_CATEGORIES: &str = r#"
+DELETE FROM category
+WHERE id NOT IN (SELECT MIN(id) FROM category GROUP BY list_id, name)
+"#;
+
+/// Name, table, columns and whether the index is unique.
+fn indexes() -> [(&'static str, DynIden, Vec<DynIden>, bool); 7] {
+    [
+        // `List::from_user_id` looks up the live list of a user.
+        (
+            "idx-list-user_id-scenario_of",
+            List::Table.into_iden(),
+            vec![List::UserId.into_iden(), List::ScenarioOf.into_iden()],
+            false,
+        ),
+        (
+            "idx-list-scenario_of",
+            List::Table.into_iden(),
+            vec![List::ScenarioOf.into_iden()],
+            false,
+        ),
+        // A list has one budget.
+        (
+            "idx-budget-list_id",
+            Budget::Table.into_iden(),
+            vec![Budget::ListId.into_iden()],
+            true,
+        ),
+        (
+            "idx-giving_bracket-budget_id",
+            GivingBracket::Table.into_iden(),
+            vec![GivingBracket::BudgetId.into_iden()],
+            false,
+        ),
+        // Category names are unique per list, the index also serves lookups by list.
+        (
+            "idx-category-list_id-name",
+            Category::Table.into_iden(),
+            vec![Category::ListId.into_iden(), Category::Name.into_iden()],
+            true,
+        ),
+        (
+            "idx-entry-category_id",
+            Entry::Table.into_iden(),
+            vec![Entry::CategoryId.into_iden()],
+            false,
+        ),
+        (
+            "idx-donation-list_id",
+            Donation::Table.into_iden(),
+            vec![Donation::ListId.into_iden()],
+            false,
+        ),
+    ]
+}
+
+/// Every table below `list` already references its parent with a cascading foreign key, from
+/// the migration that created it. The columns left without one are deliberate: `list.user_id`
+/// is a Clerk user id, and `entry.ein` and `donation.ein` may name nonprofits that are not in
+/// the `nonprofit` table, which only holds the ones we have details for.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(DEDUPLICATE_BUDGETS).await?;
+        db.execute_unprepared(MERGE_DUPLICATE_CATEGORIES).await?;
+        db.execute_unprepared(DEDUPLICATE_CATEGORIES).await?;
+
+        for (name, table, columns, unique) in indexes() {
+            let mut index = Index::create();
+            index.name(name).table(table);
+            for column in columns {
+                index.col(column);
+            }
+            if unique {
+                index.unique();
+            }
+
+            manager.create_index(index).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Deduplicated rows are not restored.
+        for (name, table, _, _) in indexes() {
+            manager
+                .drop_index(Index::drop().name(name).table(table).to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum DefaultCategory {
+    Table,
+    Key,
+}
+
+#[derive(DeriveIden)]
+pub enum Color {
+    Table,
+    Key,
+}
+
+/// Keys of the rows seeded before this migration, derived the same way `database::seed`
+/// names them, e.g. `Light Blue` becomes `light_blue`.
+const KEY_DEFAULT_CATEGORIES: &str = r#"
+UPDATE default_category SET key = LOWER(REPLACE(name, ' ', '_'))
+"#;
+
+const KEY_COLORS: &str = r#"
+UPDATE color SET key = LOWER(REPLACE(name, ' ', '_'))
+"#;
+
+/// Seeds were inserted again every time their table migration ran, keep the oldest copy.
+const DEDUPLICATE_DEFAULT_CATEGORIES: &str = r#"
+DELETE FROM default_category
+WHERE id NOT IN (SELECT MIN(id) FROM default_category GROUP BY key)
+"#;
+
+const DEDUPLICATE_COLORS: &str = r#"
+DELETE FROM color
+WHERE id NOT IN (SELECT MIN(id) FROM color GROUP BY key)
+"#;
+
+fn seeded_tables() -> [(&'static str, DynIden, DynIden); 2] {
+    [
+        (
+            "idx-default_category-key",
+            DefaultCategory::Table.into_iden(),
+            DefaultCategory::Key.into_iden(),
+        ),
+        (
+            "idx-color-key",
+            Color::Table.into_iden(),
+            Color::Key.into_iden(),
+        ),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (_, table, key) in seeded_tables() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .add_column(ColumnDef::new(key).string().not_null().default(""))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        let db = manager.get_connection();
+        db.execute_unprepared(KEY_DEFAULT_CATEGORIES).await?;
+        db.execute_unprepared(KEY_COLORS).await?;
+        db.execute_unprepared(DEDUPLICATE_DEFAULT_CATEGORIES)
+            .await?;
+        db.execute_unprepared(DEDUPLICATE_COLORS).await?;
+
+        // Seeds are upserted on their key.
+        for (name, table, key) in seeded_tables() {
+            manager
+                .create_index(
+                    Index::create()
+                        .name(name)
+                        .table(table)
+                        .col(key)
+                        .unique()
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (name, table, key) in seeded_tables() {
+            manager
+                .drop_index(Index::drop().name(name).table(table.clone()).to_owned())
+                .await?;
+            manager
+                .alter_table(Table::alter().table(table).drop_column(key).to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000013_add_indexes_and_constraints::Category;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const CASE_SENSITIVE_INDEX: &str = "idx-category-list_id-name";
+
+/// `List::apply_import` already matches names without case, the index now agrees with it.
+/// sea-query cannot express an index on `LOWER(name)`, both backends accept this one.
+const CREATE_CASE_INSENSITIVE_INDEX: &str = r#"
+CREATE UNIQUE INDEX "idx-category-list_id-lower_name" ON "category" ("list_id", LOWER("name"))
+"#;
+
+const DROP_CASE_INSENSITIVE_INDEX: &str = r#"
+DROP INDEX "idx-category-list_id-lower_name"
+"#;
+
+/// Categories that only differ in case are about to be merged into the oldest of them. An
+/// EIN listed in more than one of them keeps its oldest entry. This also drops the duplicate
+/// EINs migration 13 could leave behind when it merged same named categories.
+const DEDUPLICATE_MERGED_ENTRIES: &str = r#"
+DELETE FROM entry
+WHERE id NOT IN (
+    SELECT MIN(entry.id)
+    FROM entry
+    JOIN category ON category.id = entry.category_id
+    GROUP BY category.list_id, LOWER(category.name), entry.ein
+)
+"#;
+
+const MERGE_DUPLICATE_CATEGORIES: &str = r#"
+UPDATE entry SET category_id = (
+    SELECT MIN(keep.id)
+    FROM category AS duplicate
+    JOIN category AS keep
+        ON keep.list_id = duplicate.list_id AND LOWER(keep.name) = LOWER(duplicate.name)
+    WHERE duplicate.id = entry.category_id
+)
+WHERE category_id NOT IN (SELECT MIN(id) FROM category GROUP BY list_id, LOWER(name))
+"#;
+
+const DEDUPLICATE_CATEGORIES: &str = r#"
+DELETE FROM category
+WHERE id NOT IN (SELECT MIN(id) FROM category GROUP BY list_id, LOWER(name))
+"#;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(DEDUPLICATE_MERGED_ENTRIES).await?;
+        db.execute_unprepared(MERGE_DUPLICATE_CATEGORIES).await?;
+        db.execute_unprepared(DEDUPLICATE_CATEGORIES).await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(CASE_SENSITIVE_INDEX)
+                    .table(Category::Table)
+                    .to_owned(),
+            )
+            .await?;
+        db.execute_unprepared(CREATE_CASE_INSENSITIVE_INDEX).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Merged rows are not restored.
+        manager
+            .get_connection()
+            .execute_unprepared(DROP_CASE_INSENSITIVE_INDEX)
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name(CASE_SENSITIVE_INDEX)
+                    .table(Category::Table)
+                    .col(Category::ListId)
+                    .col(Category::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -6,6 +6,15 @@ mod m20220101_000003_create_list_table;
 mod m20220101_000004_create_budget_table;
 mod m20220101_000005_create_category_table;
 mod m20220101_000006_create_entry_table;
+mod m20220101_000007_create_nonprofit_table;
+mod m20220101_000008_create_donation_table;
+mod m20220101_000009_add_budget_giving_brackets;
+mod m20220101_000010_add_list_scenarios;
+mod m20220101_000011_create_list_event_table;
+mod m20220101_000012_add_row_versions;
+mod m20220101_000013_add_indexes_and_constraints;
+mod m20220101_000014_add_seed_keys;
+mod m20220101_000015_case_insensitive_category_names;
 
 pub struct Migrator;
 
@@ -19,6 +28,15 @@ impl MigratorTrait for Migrator {
             Box::new(m20220101_000004_create_budget_table::Migration),
             Box::new(m20220101_000005_create_category_table::Migration),
             Box::new(m20220101_000006_create_entry_table::Migration),
+            Box::new(m20220101_000007_create_nonprofit_table::Migration),
+            Box::new(m20220101_000008_create_donation_table::Migration),
+            Box::new(m20220101_000009_add_budget_giving_brackets::Migration),
+            Box::new(m20220101_000010_add_list_scenarios::Migration),
+            Box::new(m20220101_000011_create_list_event_table::Migration),
+            Box::new(m20220101_000012_add_row_versions::Migration),
+            Box::new(m20220101_000013_add_indexes_and_constraints::Migration),
+            Box::new(m20220101_000014_add_seed_keys::Migration),
+            Box::new(m20220101_000015_case_insensitive_category_names::Migration),
         ]
     }
 }
@@ -0,0 +1,361 @@
+use std::collections::BTreeMap;
+
+use actix_web::{
+    get,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web::{Data, Path},
+    HttpResponse,
+};
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfLayerReference};
+use rust_decimal::prelude::*;
+use sea_orm::{prelude::Date, *};
+use serde::{Deserialize, Serialize};
+
+use crate::clerk_middleware::current_user::CurrentUser;
+use crate::entity::donation::{Column as DonationColumn, Entity as DonationEntity};
+use crate::entity::nonprofit::{Column as NonprofitColumn, Entity as NonprofitEntity};
+use crate::list::List;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum Deductibility {
+    /// Listed in IRS Publication 78 with the given deductibility code.
+    Deductible(String),
+    NotDeductible,
+    /// The EIN is not in the loaded Publication 78 data, see `database::nonprofits`.
+    Unknown,
+}
+
+impl std::fmt::Display for Deductibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Deductibility::Deductible(code) => write!(f, "Deductible ({code})"),
+            Deductibility::NotDeductible => write!(f, "Not deductible"),
+            Deductibility::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct CharitySummary {
+    pub ein: i32,
+    pub name: Option<String>,
+    pub deductibility: Deductibility,
+
+    pub total: Decimal,
+    pub donation_count: usize,
+    pub receipts: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct TaxYearReport {
+    pub year: i32,
+
+    pub income: Decimal,
+    pub total: Decimal,
+    pub deductible_total: Decimal,
+    pub share_of_income: Decimal,
+
+    pub charities: Vec<CharitySummary>,
+}
+
+impl TaxYearReport {
+    /// Totals the donations recorded against `list` in the calendar `year`, per EIN.
+    /// Income is the list's `budget.total_value`.
+    pub async fn build(db: &DatabaseConnection, list: &List, year: i32) -> Result<Self, DbErr> {
+        let (start, end) = Date::from_ymd_opt(year, 1, 1)
+            .zip(Date::from_ymd_opt(year + 1, 1, 1))
+            .ok_or(DbErr::Custom(format!("Invalid report year {year}.")))?;
+
+        let donations = DonationEntity::find()
+            .filter(DonationColumn::ListId.eq(list.id))
+            .filter(DonationColumn::DonatedOn.gte(start))
+            .filter(DonationColumn::DonatedOn.lt(end))
+            .order_by_asc(DonationColumn::DonatedOn)
+            .all(db)
+            .await?;
+
+        let mut eins: Vec<i32> = donations.iter().map(|donation| donation.ein).collect();
+        eins.sort_unstable();
+        eins.dedup();
+
+        let nonprofits = NonprofitEntity::find()
+            .filter(NonprofitColumn::Ein.is_in(eins))
+            .all(db)
+            .await?;
+
+        let mut charities: BTreeMap<i32, CharitySummary> = BTreeMap::new();
+        donations.into_iter().for_each(|donation| {
+            let charity = charities.entry(donation.ein).or_insert_with(|| {
+                let nonprofit = nonprofits
+                    .iter()
+                    .find(|nonprofit| nonprofit.ein == donation.ein);
+
+                CharitySummary {
+                    ein: donation.ein,
+                    name: nonprofit.map(|nonprofit| nonprofit.name.clone()),
+                    deductibility: match nonprofit {
+                        Some(nonprofit) => match &nonprofit.deductibility_code {
+                            Some(code) => Deductibility::Deductible(code.clone()),
+                            None => Deductibility::NotDeductible,
+                        },
+                        None => Deductibility::Unknown,
+                    },
+                    total: Decimal::ZERO,
+                    donation_count: 0,
+                    receipts: Vec::new(),
+                }
+            });
+
+            charity.total += donation.amount;
+            charity.donation_count += 1;
+            charity.receipts.extend(donation.receipt_reference);
+        });
+
+        let charities: Vec<CharitySummary> = charities.into_values().collect();
+        let total: Decimal = charities.iter().map(|charity| charity.total).sum();
+        let deductible_total: Decimal = charities
+            .iter()
+            .filter(|charity| matches!(charity.deductibility, Deductibility::Deductible(_)))
+            .map(|charity| charity.total)
+            .sum();
+
+        let income = list.budget.total_value;
+        let share_of_income = if income > Decimal::ZERO {
+            (total / income).round_dp(4)
+        } else {
+            Decimal::ZERO
+        };
+
+        Ok(Self {
+            year,
+            income,
+            total,
+            deductible_total,
+            share_of_income,
+            charities,
+        })
+    }
+
+    pub fn to_html(&self) -> String {
+        let rows: String = self
+            .charities
+            .iter()
+            .map(|charity| {
+                format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"amount\">{}</td><td>{}</td></tr>",
+                    format_ein(charity.ein),
+                    escape_html(charity.name.as_deref().unwrap_or("")),
+                    escape_html(&charity.deductibility.to_string()),
+                    format_money(charity.total),
+                    escape_html(&charity.receipts.join(", ")),
+                )
+            })
+            .collect();
+
+        format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{year} Giving Summary</title>
+<style>
+body {{ font-family: sans-serif; margin: 2rem; }}
+table {{ border-collapse: collapse; width: 100%; }}
+th, td {{ border-bottom: 1px solid #ccc; padding: 0.4rem; text-align: left; }}
+.amount {{ text-align: right; }}
+@media print {{ body {{ margin: 0; }} }}
+</style>
+</head>
+<body>
+<h1>{year} Giving Summary</h1>
+<p>Total donated: {total}</p>
+<p>Deductible donations: {deductible_total}</p>
+<p>Income: {income}</p>
+<p>Share of income: {share}</p>
+<table>
+<thead><tr><th>EIN</th><th>Charity</th><th>Deductibility</th><th class="amount">Total</th><th>Receipts</th></tr></thead>
+<tbody>{rows}</tbody>
+</table>
+</body>
+</html>
+"#,
+            year = self.year,
+            total = format_money(self.total),
+            deductible_total = format_money(self.deductible_total),
+            income = format_money(self.income),
+            share = format_percent(self.share_of_income),
+        )
+    }
+
+    /// Renders an A4 PDF with the builtin Helvetica font, so no font files are needed.
+    pub fn to_pdf(&self) -> Result<Vec<u8>, printpdf::Error> {
+        const PAGE_WIDTH: f32 = 210.0;
+        const PAGE_HEIGHT: f32 = 297.0;
+        const MARGIN: f32 = 20.0;
+        const LINE_HEIGHT: f32 = 6.0;
+
+        let title = format!("{} Giving Summary", self.year);
+        let (doc, page, layer) =
+            PdfDocument::new(&title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+        let font = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+        let bold_font = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+
+        let mut layer = doc.get_page(page).get_layer(layer);
+        let mut y = PAGE_HEIGHT - MARGIN;
+
+        layer.use_text(&title, 18.0, Mm(MARGIN), Mm(y), &bold_font);
+        y -= LINE_HEIGHT * 2.0;
+
+        for line in [
+            format!("Total donated: {}", format_money(self.total)),
+            format!(
+                "Deductible donations: {}",
+                format_money(self.deductible_total)
+            ),
+            format!("Income: {}", format_money(self.income)),
+            format!("Share of income: {}", format_percent(self.share_of_income)),
+        ] {
+            layer.use_text(line, 11.0, Mm(MARGIN), Mm(y), &font);
+            y -= LINE_HEIGHT;
+        }
+        y -= LINE_HEIGHT;
+
+        let columns = [MARGIN, MARGIN + 25.0, MARGIN + 85.0, MARGIN + 125.0];
+        let write_row =
+            |layer: &PdfLayerReference, y: f32, cells: [String; 4], font: &IndirectFontRef| {
+                cells
+                    .into_iter()
+                    .zip(columns)
+                    .for_each(|(cell, x)| layer.use_text(cell, 10.0, Mm(x), Mm(y), font));
+            };
+
+        let header = || {
+            [
+                "EIN".to_owned(),
+                "Charity".to_owned(),
+                "Deductibility".to_owned(),
+                "Total".to_owned(),
+            ]
+        };
+        write_row(&layer, y, header(), &bold_font);
+        y -= LINE_HEIGHT;
+
+        for charity in &self.charities {
+            let receipts = if charity.receipts.is_empty() {
+                None
+            } else {
+                Some(format!("Receipts: {}", charity.receipts.join(", ")))
+            };
+            let needed = if receipts.is_some() { 2.0 } else { 1.0 } * LINE_HEIGHT;
+
+            if y - needed < MARGIN {
+                let (page, new_layer) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+                layer = doc.get_page(page).get_layer(new_layer);
+                y = PAGE_HEIGHT - MARGIN;
+                write_row(&layer, y, header(), &bold_font);
+                y -= LINE_HEIGHT;
+            }
+
+            write_row(
+                &layer,
+                y,
+                [
+                    format_ein(charity.ein),
+                    truncate(charity.name.as_deref().unwrap_or(""), 32),
+                    charity.deductibility.to_string(),
+                    format_money(charity.total),
+                ],
+                &font,
+            );
+            y -= LINE_HEIGHT;
+
+            if let Some(receipts) = receipts {
+                layer.use_text(truncate(&receipts, 90), 8.0, Mm(columns[1]), Mm(y), &font);
+                y -= LINE_HEIGHT;
+            }
+        }
+
+        doc.save_to_bytes()
+    }
+}
+
+async fn load_report(
+    db: &DatabaseConnection,
+    user_id: String,
+    year: i32,
+) -> actix_web::Result<TaxYearReport> {
+    let list = List::from_user_id(db, user_id)
+        .await
+        .ok_or_else(|| actix_web::error::ErrorNotFound("No list found for user."))?;
+
+    TaxYearReport::build(db, &list, year)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)
+}
+
+#[get("/{year}/html")]
+pub async fn report_html(
+    db: Data<DatabaseConnection>,
+    year: Path<i32>,
+    user: CurrentUser,
+) -> actix_web::Result<HttpResponse> {
+    let report = load_report(db.get_ref(), user.id, year.into_inner()).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(report.to_html()))
+}
+
+#[get("/{year}/pdf")]
+pub async fn report_pdf(
+    db: Data<DatabaseConnection>,
+    year: Path<i32>,
+    user: CurrentUser,
+) -> actix_web::Result<HttpResponse> {
+    let report = load_report(db.get_ref(), user.id, year.into_inner()).await?;
+    let pdf = report
+        .to_pdf()
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/pdf")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!(
+                "giving-summary-{}.pdf",
+                report.year
+            ))],
+        })
+        .body(pdf))
+}
+
+fn format_ein(ein: i32) -> String {
+    let ein = format!("{ein:09}");
+    format!("{}-{}", &ein[..2], &ein[2..])
+}
+
+fn format_money(value: Decimal) -> String {
+    format!("${:.2}", value.round_dp(2))
+}
+
+fn format_percent(value: Decimal) -> String {
+    format!("{:.2}%", (value * Decimal::ONE_HUNDRED).round_dp(2))
+}
+
+fn truncate(value: &str, max_chars: usize) -> String {
+    if value.chars().count() <= max_chars {
+        return value.to_owned();
+    }
+    let mut truncated: String = value.chars().take(max_chars - 3).collect();
+    truncated.push_str("...");
+    truncated
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::list::List;
+
+/// Version of the tax table data file format this module understands.
+pub const TAX_TABLE_VERSION: u32 = 1;
+
+const BUNDLED_TAX_TABLE: &str = include_str!("../../data/tax/us_federal_2023.json");
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FilingStatus {
+    Single,
+    MarriedFilingJointly,
+    MarriedFilingSeparately,
+    HeadOfHousehold,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct TaxBracket {
+    /// Taxable income at which `rate` starts to apply.
+    pub threshold: Decimal,
+    pub rate: Decimal,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct FilingStatusTable {
+    pub standard_deduction: Decimal,
+    pub brackets: Vec<TaxBracket>,
+}
+
+/// Limits on deductible cash gifts as a share of AGI.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct CharitableCaps {
+    pub public_charity_cash: Decimal,
+    pub private_foundation_cash: Decimal,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct TaxTable {
+    pub version: u32,
+    pub tax_year: i32,
+    pub charitable_caps: CharitableCaps,
+    pub filing_statuses: HashMap<FilingStatus, FilingStatusTable>,
+}
+
+#[derive(Debug)]
+pub enum TaxTableError {
+    Parse(serde_json::Error),
+    UnsupportedVersion(u32),
+    MissingFilingStatus(FilingStatus),
+    InvalidBrackets(FilingStatus),
+}
+
+impl std::fmt::Display for TaxTableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaxTableError::Parse(error) => write!(f, "Unable to parse tax table: {error}"),
+            TaxTableError::UnsupportedVersion(version) => write!(
+                f,
+                "Unsupported tax table version {version}, expected {TAX_TABLE_VERSION}."
+            ),
+            TaxTableError::MissingFilingStatus(status) => {
+                write!(f, "Tax table has no entry for {status:?}.")
+            }
+            TaxTableError::InvalidBrackets(status) => write!(
+                f,
+                "Tax brackets for {status:?} must start at 0 and have increasing thresholds."
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TaxTableError {}
+
+/// What the user tells us about their return. `agi` is usually the budget's `total_value`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct TaxProfile {
+    pub filing_status: FilingStatus,
+    pub agi: Decimal,
+    /// Itemizable deductions other than charity, e.g. mortgage interest and state taxes.
+    pub other_itemized_deductions: Decimal,
+    /// Part of the planned giving that goes to private foundations, which has a lower AGI cap.
+    pub private_foundation_giving: Decimal,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct DeductionOutcome {
+    pub deduction: Decimal,
+    pub taxable_income: Decimal,
+    pub tax: Decimal,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct BunchingComparison {
+    /// Tax over two years when the planned giving is made every year.
+    pub annual_tax: Decimal,
+    /// Tax over two years when both years of giving are made in the first year.
+    pub bunched_tax: Decimal,
+    pub savings: Decimal,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct TaxEstimate {
+    pub tax_year: i32,
+
+    pub giving: Decimal,
+    pub deductible_giving: Decimal,
+    /// Giving over the AGI caps. It can be carried forward for up to five years.
+    pub carryover: Decimal,
+
+    pub standard: DeductionOutcome,
+    pub itemized: DeductionOutcome,
+    pub itemize: bool,
+
+    pub marginal_rate: Decimal,
+    pub tax_savings: Decimal,
+    pub after_tax_cost: Decimal,
+
+    pub bunching: BunchingComparison,
+}
+
+impl TaxTable {
+    /// The table shipped with the app, `data/tax/us_federal_2023.json`.
+    pub fn bundled() -> TaxTable {
+        TaxTable::from_json(BUNDLED_TAX_TABLE).expect("Bundled tax table is invalid.")
+    }
+
+    pub fn from_json(data: &str) -> Result<TaxTable, TaxTableError> {
+        let table: TaxTable = serde_json::from_str(data).map_err(TaxTableError::Parse)?;
+
+        if table.version != TAX_TABLE_VERSION {
+            return Err(TaxTableError::UnsupportedVersion(table.version));
+        }
+
+        for status in [
+            FilingStatus::Single,
+            FilingStatus::MarriedFilingJointly,
+            FilingStatus::MarriedFilingSeparately,
+            FilingStatus::HeadOfHousehold,
+        ] {
+            let status_table = table
+                .filing_statuses
+                .get(&status)
+                .ok_or(TaxTableError::MissingFilingStatus(status))?;
+
+            let starts_at_zero = status_table
+                .brackets
+                .first()
+                .is_some_and(|bracket| bracket.threshold == Decimal::ZERO);
+            let increasing = status_table
+                .brackets
+                .windows(2)
+                .all(|pair| pair[0].threshold < pair[1].threshold);
+
+            if !starts_at_zero || !increasing {
+                return Err(TaxTableError::InvalidBrackets(status));
+            }
+        }
+
+        Ok(table)
+    }
+
+    fn status(&self, status: FilingStatus) -> &FilingStatusTable {
+        &self.filing_statuses[&status]
+    }
+
+    /// Tax owed on `taxable_income` using the marginal brackets for `status`.
+    pub fn tax(&self, status: FilingStatus, taxable_income: Decimal) -> Decimal {
+        let brackets = &self.status(status).brackets;
+
+        brackets
+            .iter()
+            .enumerate()
+            .filter(|(_, bracket)| taxable_income > bracket.threshold)
+            .map(|(index, bracket)| {
+                let ceiling = brackets
+                    .get(index + 1)
+                    .map_or(taxable_income, |next| next.threshold.min(taxable_income));
+                (ceiling - bracket.threshold) * bracket.rate
+            })
+            .sum::<Decimal>()
+            .round_dp(2)
+    }
+
+    pub fn marginal_rate(&self, status: FilingStatus, taxable_income: Decimal) -> Decimal {
+        self.status(status)
+            .brackets
+            .iter()
+            .rev()
+            .find(|bracket| taxable_income > bracket.threshold)
+            .map_or(Decimal::ZERO, |bracket| bracket.rate)
+    }
+
+    /// Splits `giving` into the deductible part and the carryover over the AGI caps.
+    pub fn capped_giving(&self, profile: &TaxProfile, giving: Decimal) -> (Decimal, Decimal) {
+        let caps = &self.charitable_caps;
+
+        let private_foundation_giving = profile.private_foundation_giving.min(giving);
+        let public_charity_giving = giving - private_foundation_giving;
+
+        let public_charity_cap = profile.agi * caps.public_charity_cash;
+        let deductible_public = public_charity_giving.min(public_charity_cap);
+
+        // Private foundation gifts are limited to their own cap and to what is left
+        // under the overall public charity cap.
+        let private_foundation_cap = (profile.agi * caps.private_foundation_cash)
+            .min(public_charity_cap - deductible_public);
+        let deductible_private = private_foundation_giving.min(private_foundation_cap);
+
+        let deductible = (deductible_public + deductible_private).round_dp(2);
+        (deductible, giving - deductible)
+    }
+
+    fn outcome(&self, profile: &TaxProfile, deduction: Decimal) -> DeductionOutcome {
+        let taxable_income = (profile.agi - deduction).max(Decimal::ZERO);
+
+        DeductionOutcome {
+            deduction,
+            taxable_income,
+            tax: self.tax(profile.filing_status, taxable_income),
+        }
+    }
+
+    /// Tax for one year with `giving` donated, taking whichever deduction is larger.
+    fn best_tax(&self, profile: &TaxProfile, giving: Decimal) -> Decimal {
+        let (deductible_giving, _) = self.capped_giving(profile, giving);
+        let standard_deduction = self.status(profile.filing_status).standard_deduction;
+        let itemized_deduction = profile.other_itemized_deductions + deductible_giving;
+
+        self.outcome(profile, standard_deduction.max(itemized_deduction))
+            .tax
+    }
+
+    pub fn estimate(&self, profile: &TaxProfile, giving: Decimal) -> TaxEstimate {
+        let (deductible_giving, carryover) = self.capped_giving(profile, giving);
+
+        let standard = self.outcome(
+            profile,
+            self.status(profile.filing_status).standard_deduction,
+        );
+        let itemized = self.outcome(
+            profile,
+            profile.other_itemized_deductions + deductible_giving,
+        );
+        let itemize = itemized.tax < standard.tax;
+        let chosen = if itemize { &itemized } else { &standard };
+
+        let tax_without_giving = self.best_tax(profile, Decimal::ZERO);
+        let tax_savings = tax_without_giving - chosen.tax;
+
+        // Any carryover from the bunched year is not applied to the second year.
+        let annual_tax = chosen.tax * Decimal::TWO;
+        let bunched_tax = self.best_tax(profile, giving * Decimal::TWO) + tax_without_giving;
+
+        TaxEstimate {
+            tax_year: self.tax_year,
+            giving,
+            deductible_giving,
+            carryover,
+            marginal_rate: self.marginal_rate(profile.filing_status, chosen.taxable_income),
+            tax_savings,
+            after_tax_cost: giving - tax_savings,
+            standard,
+            itemized,
+            itemize,
+            bunching: BunchingComparison {
+                annual_tax,
+                bunched_tax,
+                savings: annual_tax - bunched_tax,
+            },
+        }
+    }
+}
+
+impl List {
+    /// Total planned giving across the included categories.
+    pub fn planned_giving(&self) -> Decimal {
+        self.process_list().allocated_value()
+    }
+
+    /// Runs the allocation and estimates the after-tax cost of giving it.
+    pub fn estimate_taxes(&self, table: &TaxTable, profile: &TaxProfile) -> TaxEstimate {
+        table.estimate(profile, self.planned_giving())
+    }
+}
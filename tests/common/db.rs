@@ -0,0 +1,54 @@
+use leptos_start::config::{DatabaseBackend, DatabaseConfig, PoolConfig};
+use leptos_start::database::init_database;
+use leptos_start::list::{BudgetMode, IncomeBasis, List};
+use rust_decimal::Decimal;
+use sea_orm::DatabaseConnection;
+use std::time::Duration;
+
+use super::{bracket, category, entry};
+
+/// An in-memory SQLite database. It keeps a single connection, every new connection would
+/// open a database of its own.
+pub fn config(auto_migrate: bool) -> DatabaseConfig {
+    DatabaseConfig {
+        backend: DatabaseBackend::Sqlite,
+        url: "sqlite::memory:".to_owned(),
+        schema_name: String::new(),
+        pool: PoolConfig {
+            max_connections: 1,
+            min_connections: 1,
+            connect_timeout: Duration::from_secs(8),
+            acquire_timeout: Duration::from_secs(8),
+            idle_timeout: Duration::from_secs(600),
+            max_lifetime: Duration::from_secs(1800),
+        },
+        connect_retries: 0,
+        auto_migrate,
+    }
+}
+
+/// A fresh in-memory SQLite database with every migration applied, one per test.
+pub async fn database() -> DatabaseConnection {
+    init_database(&config(true)).await
+}
+
+/// The live list of `user_id` with two categories, brackets and overrides, saved once.
+pub async fn saved_list(db: &DatabaseConnection, user_id: &str) -> List {
+    let mut list = List::init_list(db, user_id.to_owned()).await.unwrap();
+
+    list.budget.mode = BudgetMode::Brackets;
+    list.budget.income_basis = IncomeBasis::Net;
+    list.budget.net_value = Some(Decimal::new(42_000, 0));
+    list.budget.brackets = vec![bracket(0, 5), bracket(40_000, 15)];
+
+    let mut overridden = entry(222);
+    overridden.value_override = Some(Decimal::new(250, 0));
+    overridden.enabled = false;
+    let mut health = category("Global Health", vec![entry(111), overridden]);
+    health.multiplier = Decimal::new(15, 1);
+    let mut animals = category("Animal Welfare", vec![entry(333)]);
+    animals.percent_override = Some(Decimal::new(25, 2));
+    list.categories = vec![health, animals];
+
+    list.save(db, user_id).await.unwrap()
+}
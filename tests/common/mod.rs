@@ -0,0 +1,80 @@
+//! Fixtures shared by the integration tests. Each test crate uses only some of them.
+#![allow(dead_code)]
+
+#[cfg(feature = "sqlite")]
+pub mod db;
+
+use leptos_start::list::{Category, Entry, GivingBracket, List};
+use rust_decimal::Decimal;
+
+/// A new category for `save`, id 0 is never stored so it is inserted.
+pub fn category(name: &str, entries: Vec<Entry>) -> Category {
+    Category {
+        id: 0,
+        list_id: 0,
+        name: name.to_owned(),
+        entries,
+        multiplier: Decimal::ONE,
+        percent_override: None,
+        value_override: None,
+        enabled: true,
+        version: 0,
+    }
+}
+
+/// A new entry for `save`.
+pub fn entry(ein: i32) -> Entry {
+    Entry {
+        id: 0,
+        category_id: 0,
+        ein,
+        multiplier: Decimal::ONE,
+        percent_override: None,
+        value_override: None,
+        enabled: true,
+        version: 0,
+    }
+}
+
+/// A category of list 1 as loaded from the database.
+pub fn stored_category(id: i32, name: &str, entries: Vec<Entry>) -> Category {
+    Category {
+        id,
+        list_id: 1,
+        version: 1,
+        ..category(name, entries)
+    }
+}
+
+/// An entry of `category_id` as loaded from the database.
+pub fn stored_entry(id: i32, category_id: i32, ein: i32) -> Entry {
+    Entry {
+        id,
+        category_id,
+        version: 1,
+        ..entry(ein)
+    }
+}
+
+pub fn bracket(threshold: i64, percent: i64) -> GivingBracket {
+    GivingBracket {
+        id: 0,
+        budget_id: 0,
+        threshold: Decimal::new(threshold, 0),
+        percent: Decimal::new(percent, 2),
+    }
+}
+
+pub fn category_named<'a>(list: &'a List, name: &str) -> &'a Category {
+    list.categories
+        .iter()
+        .find(|category| category.name == name)
+        .unwrap()
+}
+
+/// The EINs of `category`, sorted.
+pub fn eins(category: &Category) -> Vec<i32> {
+    let mut eins: Vec<i32> = category.entries.iter().map(|entry| entry.ein).collect();
+    eins.sort();
+    eins
+}
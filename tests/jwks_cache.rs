@@ -0,0 +1,178 @@
+use actix_web::{web, App, HttpResponse, HttpServer};
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use leptos_start::clerk_middleware::jwks::{JwksCache, JwksCacheConfig};
+use std::{
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// What the mock Clerk JWKS endpoint currently serves.
+#[derive(Default)]
+struct MockJwks {
+    kids: Vec<&'static str>,
+    failing: bool,
+    hits: usize,
+}
+
+async fn serve_jwks(mock: web::Data<Mutex<MockJwks>>) -> HttpResponse {
+    let mut mock = mock.lock().unwrap();
+    mock.hits += 1;
+
+    if mock.failing {
+        return HttpResponse::ServiceUnavailable().finish();
+    }
+
+    HttpResponse::Ok().json(JwkSet {
+        keys: mock.kids.iter().map(|kid| key(kid)).collect(),
+    })
+}
+
+fn key(kid: &str) -> Jwk {
+    serde_json::from_value(serde_json::json!({
+        "use": "sig",
+        "kty": "RSA",
+        "kid": kid,
+        "alg": "RS256",
+        "n": "sXchDaQebHnPiGvyDOAT4saGEUetSyo9MKLOoWFsueri23bOdgWp4Dy1Wl",
+        "e": "AQAB",
+    }))
+    .unwrap()
+}
+
+/// Starts a JWKS server on a free local port serving `kids`.
+fn mock_server(kids: Vec<&'static str>) -> (Arc<Mutex<MockJwks>>, JwksCacheConfig) {
+    let mock = web::Data::new(Mutex::new(MockJwks {
+        kids,
+        ..Default::default()
+    }));
+    let state = mock.clone().into_inner();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(mock.clone())
+            .route("/v1/jwks", web::get().to(serve_jwks))
+    })
+    .workers(1)
+    .listen(listener)
+    .unwrap()
+    .run();
+    actix_web::rt::spawn(server);
+
+    let config = JwksCacheConfig {
+        url: format!("http://{addr}/v1/jwks"),
+        secret_key: None,
+        ttl: Duration::from_secs(60),
+        min_refresh_interval: Duration::ZERO,
+        max_stale: Duration::from_secs(60),
+        timeout: Duration::from_secs(1),
+    };
+
+    (state, config)
+}
+
+fn kids(jwks: &JwkSet) -> Vec<&str> {
+    jwks.keys
+        .iter()
+        .filter_map(|key| key.common.key_id.as_deref())
+        .collect()
+}
+
+#[actix_web::test]
+async fn fetches_once_within_ttl() {
+    let (mock, config) = mock_server(vec!["a"]);
+    let cache = JwksCache::new(config);
+
+    for _ in 0..3 {
+        let jwks = cache.key_set(Some("a")).await.unwrap();
+        assert_eq!(kids(&jwks), ["a"]);
+    }
+    // Clones share the cached set, like the middleware of each worker.
+    cache.clone().key_set(None).await.unwrap();
+
+    assert_eq!(mock.lock().unwrap().hits, 1);
+}
+
+#[actix_web::test]
+async fn refreshes_on_unknown_kid() {
+    let (mock, config) = mock_server(vec!["a"]);
+    let cache = JwksCache::new(config);
+    cache.key_set(Some("a")).await.unwrap();
+
+    mock.lock().unwrap().kids = vec!["a", "b"];
+    let jwks = cache.key_set(Some("b")).await.unwrap();
+
+    assert_eq!(kids(&jwks), ["a", "b"]);
+    assert_eq!(mock.lock().unwrap().hits, 2);
+}
+
+#[actix_web::test]
+async fn throttles_unknown_kid_refreshes() {
+    let (mock, config) = mock_server(vec!["a"]);
+    let cache = JwksCache::new(JwksCacheConfig {
+        min_refresh_interval: Duration::from_secs(60),
+        ..config
+    });
+    cache.key_set(Some("a")).await.unwrap();
+
+    for _ in 0..3 {
+        let jwks = cache.key_set(Some("forged")).await.unwrap();
+        assert_eq!(kids(&jwks), ["a"]);
+    }
+
+    assert_eq!(mock.lock().unwrap().hits, 1);
+}
+
+#[actix_web::test]
+async fn serves_stale_keys_while_clerk_is_down() {
+    let (mock, config) = mock_server(vec!["a"]);
+    let cache = JwksCache::new(JwksCacheConfig {
+        ttl: Duration::ZERO,
+        ..config
+    });
+    cache.key_set(Some("a")).await.unwrap();
+
+    mock.lock().unwrap().failing = true;
+    let jwks = cache.key_set(Some("a")).await.unwrap();
+    assert_eq!(kids(&jwks), ["a"]);
+
+    // A kid miss fetches right away and falls back to the cached set as well.
+    let jwks = cache.key_set(Some("b")).await.unwrap();
+    assert_eq!(kids(&jwks), ["a"]);
+}
+
+#[actix_web::test]
+async fn revalidates_expired_keys_in_the_background() {
+    let (mock, config) = mock_server(vec!["a"]);
+    let cache = JwksCache::new(JwksCacheConfig {
+        ttl: Duration::from_millis(50),
+        ..config
+    });
+    cache.key_set(Some("a")).await.unwrap();
+
+    mock.lock().unwrap().kids = vec!["a", "b"];
+    actix_web::rt::time::sleep(Duration::from_millis(100)).await;
+
+    // The stale set is returned at once, the refresh lands afterwards.
+    let jwks = cache.key_set(Some("a")).await.unwrap();
+    assert_eq!(kids(&jwks), ["a"]);
+
+    actix_web::rt::time::sleep(Duration::from_millis(200)).await;
+    let jwks = cache.key_set(Some("a")).await.unwrap();
+    assert_eq!(kids(&jwks), ["a", "b"]);
+    assert_eq!(mock.lock().unwrap().hits, 2);
+}
+
+#[actix_web::test]
+async fn fails_without_usable_keys() {
+    let (mock, config) = mock_server(vec!["a"]);
+    mock.lock().unwrap().failing = true;
+    let cache = JwksCache::new(config);
+
+    assert!(cache.key_set(Some("a")).await.is_err());
+
+    mock.lock().unwrap().failing = false;
+    assert!(cache.key_set(Some("a")).await.is_ok());
+}
@@ -0,0 +1,233 @@
+use jsonwebtoken::{encode, jwk::JwkSet, Algorithm, EncodingKey, Header};
+use leptos_start::clerk_middleware::{validate_jwt, AuthError, ClerkJwt, JwtValidation};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const ISSUER: &str = "https://clerk.example.com";
+const ORIGIN: &str = "https://donationengine.example.com";
+
+/// Public halves of the keys in `tests/keys`, generated with openssl for these tests only.
+fn jwks() -> JwkSet {
+    serde_json::from_str(include_str!("keys/jwks.json")).unwrap()
+}
+
+fn now() -> i32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i32
+}
+
+fn claims() -> ClerkJwt {
+    ClerkJwt {
+        azp: Some(ORIGIN.to_owned()),
+        exp: now() + 60,
+        iat: now(),
+        iss: ISSUER.to_owned(),
+        nbf: now() - 10,
+        sid: "sess_test".to_owned(),
+        sub: "user_test".to_owned(),
+    }
+}
+
+fn sign(kid: &str, claims: &ClerkJwt) -> String {
+    let (algorithm, key) = match kid {
+        "rsa" => (
+            Algorithm::RS256,
+            EncodingKey::from_rsa_pem(include_bytes!("keys/rsa.pem")).unwrap(),
+        ),
+        "ec" => (
+            Algorithm::ES256,
+            EncodingKey::from_ec_pem(include_bytes!("keys/ec.pem")).unwrap(),
+        ),
+        _ => (
+            Algorithm::EdDSA,
+            EncodingKey::from_ed_pem(include_bytes!("keys/ed25519.pem")).unwrap(),
+        ),
+    };
+
+    let header = Header {
+        kid: Some(kid.to_owned()),
+        ..Header::new(algorithm)
+    };
+    encode(&header, claims, &key).unwrap()
+}
+
+fn rules() -> JwtValidation {
+    JwtValidation {
+        issuer: Some(ISSUER.to_owned()),
+        authorized_parties: Some(vec![ORIGIN.to_owned()]),
+        leeway: 5,
+    }
+}
+
+#[test]
+fn accepts_rs256_es256_and_eddsa() {
+    for kid in ["rsa", "ec", "ed25519"] {
+        let token = sign(kid, &claims());
+
+        let claims = validate_jwt(&token, &jwks(), &rules()).unwrap();
+        assert_eq!(claims.sub, "user_test", "{kid}");
+    }
+}
+
+#[test]
+fn rejects_expired_tokens_outside_leeway() {
+    let expired = ClerkJwt {
+        exp: now() - 3,
+        ..claims()
+    };
+    assert!(validate_jwt(&sign("ec", &expired), &jwks(), &rules()).is_ok());
+
+    let expired = ClerkJwt {
+        exp: now() - 30,
+        ..claims()
+    };
+    assert_eq!(
+        validate_jwt(&sign("ec", &expired), &jwks(), &rules()).unwrap_err(),
+        AuthError::Expired
+    );
+}
+
+#[test]
+fn rejects_tokens_not_valid_yet() {
+    let early = ClerkJwt {
+        nbf: now() + 30,
+        ..claims()
+    };
+
+    assert_eq!(
+        validate_jwt(&sign("rsa", &early), &jwks(), &rules()).unwrap_err(),
+        AuthError::NotYetValid
+    );
+}
+
+#[test]
+fn checks_issuer_when_configured() {
+    let foreign = ClerkJwt {
+        iss: "https://clerk.attacker.example".to_owned(),
+        ..claims()
+    };
+    let token = sign("ed25519", &foreign);
+
+    assert_eq!(
+        validate_jwt(&token, &jwks(), &rules()).unwrap_err(),
+        AuthError::InvalidIssuer
+    );
+
+    let any_issuer = JwtValidation {
+        issuer: None,
+        ..rules()
+    };
+    assert!(validate_jwt(&token, &jwks(), &any_issuer).is_ok());
+}
+
+#[test]
+fn checks_authorized_party() {
+    let other_origin = ClerkJwt {
+        azp: Some("https://evil.example".to_owned()),
+        ..claims()
+    };
+    assert_eq!(
+        validate_jwt(&sign("rsa", &other_origin), &jwks(), &rules()).unwrap_err(),
+        AuthError::UnauthorizedParty
+    );
+
+    let no_origin = ClerkJwt {
+        azp: None,
+        ..claims()
+    };
+    assert_eq!(
+        validate_jwt(&sign("rsa", &no_origin), &jwks(), &rules()).unwrap_err(),
+        AuthError::MissingParty
+    );
+    assert_eq!(AuthError::MissingParty.code(), "missing_party");
+
+    let any_party = JwtValidation {
+        authorized_parties: None,
+        ..rules()
+    };
+    assert!(validate_jwt(&sign("rsa", &no_origin), &jwks(), &any_party).is_ok());
+}
+
+#[test]
+fn rejects_unknown_keys() {
+    let token = sign("rsa", &claims());
+    let jwks = JwkSet {
+        keys: jwks()
+            .keys
+            .into_iter()
+            .filter(|key| key.common.key_id.as_deref() != Some("rsa"))
+            .collect(),
+    };
+
+    assert_eq!(
+        validate_jwt(&token, &jwks, &rules()).unwrap_err(),
+        AuthError::UnknownKey
+    );
+}
+
+#[test]
+fn rejects_tokens_without_kid() {
+    let token = encode(
+        &Header::new(Algorithm::RS256),
+        &claims(),
+        &EncodingKey::from_rsa_pem(include_bytes!("keys/rsa.pem")).unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        validate_jwt(&token, &jwks(), &rules()).unwrap_err(),
+        AuthError::UnknownKey
+    );
+}
+
+#[test]
+fn rejects_symmetric_keys() {
+    let jwks: JwkSet = serde_json::from_value(serde_json::json!({
+        "keys": [{ "kty": "oct", "kid": "rsa", "alg": "HS256", "k": "c2VjcmV0" }]
+    }))
+    .unwrap();
+
+    assert_eq!(
+        validate_jwt(&sign("rsa", &claims()), &jwks, &rules()).unwrap_err(),
+        AuthError::UnsupportedKey
+    );
+}
+
+#[test]
+fn rejects_algorithm_mismatch() {
+    // Signed with the EC key but claiming the RSA key's kid.
+    let key = EncodingKey::from_ec_pem(include_bytes!("keys/ec.pem")).unwrap();
+    let header = Header {
+        kid: Some("rsa".to_owned()),
+        ..Header::new(Algorithm::ES256)
+    };
+    let token = encode(&header, &claims(), &key).unwrap();
+
+    assert_eq!(
+        validate_jwt(&token, &jwks(), &rules()).unwrap_err(),
+        AuthError::AlgorithmMismatch
+    );
+}
+
+#[test]
+fn rejects_tampered_tokens() {
+    let token = sign("ec", &claims());
+    let forged = ClerkJwt {
+        sub: "user_other".to_owned(),
+        ..claims()
+    };
+    let forged_payload = sign("ec", &forged).split('.').nth(1).unwrap().to_owned();
+
+    let mut parts: Vec<&str> = token.split('.').collect();
+    parts[1] = &forged_payload;
+
+    assert_eq!(
+        validate_jwt(&parts.join("."), &jwks(), &rules()).unwrap_err(),
+        AuthError::InvalidSignature
+    );
+    assert_eq!(
+        validate_jwt("not a jwt", &jwks(), &rules()).unwrap_err(),
+        AuthError::MalformedToken
+    );
+}
@@ -0,0 +1,41 @@
+use leptos_start::list::SaveError;
+use rust_decimal::Decimal;
+
+mod common;
+
+use common::db::{database, saved_list};
+use common::{category, category_named, eins, entry};
+
+#[actix_web::test]
+async fn a_conflicting_save_merges_onto_the_stored_list() {
+    let db = database().await;
+    let opened = saved_list(&db, "user_a").await;
+
+    // Another tab raises the income and saves first.
+    let mut other_tab = opened.clone();
+    other_tab.budget.net_value = Some(Decimal::new(60_000, 0));
+    other_tab.save(&db, "user_a").await.unwrap();
+
+    let mut local = opened;
+    local
+        .categories
+        .iter_mut()
+        .find(|category| category.name == "Animal Welfare")
+        .unwrap()
+        .entries
+        .push(entry(444));
+    local.categories.push(category("Culture", vec![entry(555)]));
+    let stored = match local.save(&db, "user_a").await {
+        Err(SaveError::Conflict(stored)) => *stored,
+        other => panic!("expected a conflict, got {other:?}"),
+    };
+
+    let merged = local.merge_onto(&stored);
+    assert_eq!(merged.version, stored.version);
+    let saved = merged.save(&db, "user_a").await.unwrap();
+
+    assert_eq!(saved.budget.net_value, Some(Decimal::new(60_000, 0)));
+    assert_eq!(eins(category_named(&saved, "Global Health")), [111, 222]);
+    assert_eq!(eins(category_named(&saved, "Animal Welfare")), [333, 444]);
+    assert_eq!(eins(category_named(&saved, "Culture")), [555]);
+}
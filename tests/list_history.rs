@@ -0,0 +1,145 @@
+use leptos_start::entity::list_event;
+use leptos_start::list::history::{EventOrigin, EventState};
+use leptos_start::list::{List, SaveError};
+use rust_decimal::Decimal;
+use sea_orm::{EntityTrait, PaginatorTrait};
+
+mod common;
+
+use common::db::{database, saved_list};
+use common::entry;
+
+/// `list` without row versions and in id order, to compare the rows an undo or redo restored.
+fn unversioned(list: &List) -> List {
+    let mut list = list.clone();
+    list.version = 0;
+    list.budget.version = 0;
+    list.categories.sort_by_key(|category| category.id);
+    for category in &mut list.categories {
+        category.version = 0;
+        category.entries.sort_by_key(|entry| entry.id);
+        for entry in &mut category.entries {
+            entry.version = 0;
+        }
+    }
+    list
+}
+
+#[actix_web::test]
+async fn save_undo_and_redo_round_trip() {
+    let db = database().await;
+    let before = saved_list(&db, "user_a").await;
+
+    let mut edited = before.clone();
+    edited
+        .categories
+        .retain(|category| category.name == "Global Health");
+    edited.categories[0].multiplier = Decimal::new(3, 0);
+    edited.categories[0].entries.push(entry(444));
+    edited.budget.net_value = Some(Decimal::new(50_000, 0));
+    let after = edited.save(&db, "user_a").await.unwrap();
+
+    let undone = after.undo(&db, "user_b").await.unwrap().unwrap();
+    assert_eq!(undone.version, after.version + 1);
+    assert_eq!(unversioned(&undone), unversioned(&before));
+
+    let redone = undone.redo(&db, "user_a").await.unwrap().unwrap();
+    assert_eq!(redone.version, undone.version + 1);
+    assert_eq!(unversioned(&redone), unversioned(&after));
+    assert_eq!(redone.redo(&db, "user_a").await.unwrap(), None);
+
+    // Newest first: the redo, the undo, then the edit and the first save.
+    let history = List::history(&db, before.id).await.unwrap();
+    let change_sets: Vec<_> = history
+        .iter()
+        .map(|event| {
+            (
+                event.change_set,
+                event.actor.as_str(),
+                event.origin,
+                event.origin_change_set,
+                event.state,
+            )
+        })
+        .collect();
+    let edit = history
+        .iter()
+        .find(|event| event.origin == EventOrigin::Save)
+        .unwrap()
+        .change_set;
+    assert!(change_sets.contains(&(
+        edit + 1,
+        "user_b",
+        EventOrigin::Undo,
+        Some(edit),
+        EventState::Applied
+    )));
+    assert!(change_sets.contains(&(
+        edit + 2,
+        "user_a",
+        EventOrigin::Redo,
+        Some(edit),
+        EventState::Applied
+    )));
+    assert!(history
+        .iter()
+        .filter(|event| event.origin == EventOrigin::Save)
+        .all(|event| event.state == EventState::Applied));
+
+    // The undo reverted every row the edit changed, and the redo wrote them again.
+    let count = |change_set: i32| {
+        history
+            .iter()
+            .filter(|event| event.change_set == change_set)
+            .count()
+    };
+    assert_eq!(count(edit + 1), count(edit));
+    assert_eq!(count(edit + 2), count(edit));
+    let reinserted = history
+        .iter()
+        .find(|event| {
+            event.change_set == edit + 1 && event.before.is_none() && event.after.is_some()
+        })
+        .unwrap();
+    assert_eq!(
+        reinserted.after.as_ref().unwrap()["version"],
+        undone.version
+    );
+
+    // Undo skips its own events and reverts the edit again.
+    let undone = redone.undo(&db, "user_a").await.unwrap().unwrap();
+    assert_eq!(unversioned(&undone), unversioned(&before));
+}
+
+#[actix_web::test]
+async fn undo_and_redo_refuse_stale_versions() {
+    let db = database().await;
+    let before = saved_list(&db, "user_a").await;
+    let mut edited = before.clone();
+    edited.categories.clear();
+    let after = edited.save(&db, "user_a").await.unwrap();
+    let events = list_event::Entity::find().count(&db).await.unwrap();
+
+    match before.undo(&db, "user_a").await {
+        Err(SaveError::Conflict(stored)) => assert_eq!(*stored, after),
+        other => panic!("expected a conflict, got {other:?}"),
+    }
+    assert_eq!(List::from_id(&db, after.id).await, Some(after.clone()));
+    assert_eq!(list_event::Entity::find().count(&db).await.unwrap(), events);
+
+    let undone = after.undo(&db, "user_a").await.unwrap().unwrap();
+    match after.redo(&db, "user_a").await {
+        Err(SaveError::Conflict(stored)) => assert_eq!(*stored, undone),
+        other => panic!("expected a conflict, got {other:?}"),
+    }
+    assert!(matches!(
+        after.save(&db, "user_a").await,
+        Err(SaveError::Conflict(_))
+    ));
+
+    // Nothing to undo leaves the version alone.
+    let empty = List::init_list(&db, "user_b".to_owned()).await.unwrap();
+    assert_eq!(empty.undo(&db, "user_b").await.unwrap(), None);
+    assert_eq!(empty.redo(&db, "user_b").await.unwrap(), None);
+    assert_eq!(List::from_id(&db, empty.id).await, Some(empty));
+}
@@ -0,0 +1,435 @@
+use leptos_start::database::{init_database, migrate, MigrateCommand};
+use leptos_start::entity::{budget, category, entry, giving_bracket, list, list_event};
+use leptos_start::list::{Budget, BudgetMode, GivingBracket, IncomeBasis, List, SaveError};
+use rust_decimal::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, NotSet, PaginatorTrait, QueryFilter, QueryOrder,
+    Set,
+};
+
+mod common;
+
+use common::db::{config, database, saved_list};
+use common::{bracket, category, category_named, eins, entry};
+
+#[actix_web::test]
+async fn init_list_creates_the_live_list_and_its_budget() {
+    let db = database().await;
+
+    let list = List::init_list(&db, "user_a".to_owned()).await.unwrap();
+
+    assert_eq!(list.user_id, "user_a");
+    assert_eq!(list.scenario_of, None);
+    assert_eq!(list.version, 1);
+    assert_eq!(list.budget.list_id, list.id);
+    assert_eq!(list.budget.total_value, Decimal::new(50_000, 0));
+    assert_eq!(list.budget.donation_percent, Decimal::new(10, 2));
+    assert_eq!(list.budget.mode, BudgetMode::Percent);
+    assert_eq!(list.budget.income_basis, IncomeBasis::Gross);
+    assert!(list.budget.brackets.is_empty());
+    assert!(list.categories.is_empty());
+}
+
+#[actix_web::test]
+async fn from_user_id_loads_the_live_list_of_that_user() {
+    let db = database().await;
+    let list_a = saved_list(&db, "user_a").await;
+    let list_b = List::init_list(&db, "user_b".to_owned()).await.unwrap();
+    list_a
+        .fork_scenario(&db, "Plan B".to_owned(), "user_a")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        List::from_user_id(&db, "user_a".to_owned()).await,
+        Some(list_a)
+    );
+    assert_eq!(
+        List::from_user_id(&db, "user_b".to_owned()).await,
+        Some(list_b)
+    );
+    assert_eq!(List::from_user_id(&db, "user_c".to_owned()).await, None);
+}
+
+#[actix_web::test]
+async fn save_round_trips_budget_categories_and_entries() {
+    let db = database().await;
+
+    let saved = saved_list(&db, "user_a").await;
+
+    assert_eq!(saved.version, 2);
+    assert_eq!(saved.budget.mode, BudgetMode::Brackets);
+    assert_eq!(saved.budget.income_basis, IncomeBasis::Net);
+    assert_eq!(saved.budget.net_value, Some(Decimal::new(42_000, 0)));
+    let brackets: Vec<(Decimal, Decimal)> = saved
+        .budget
+        .brackets
+        .iter()
+        .map(|bracket| (bracket.threshold, bracket.percent))
+        .collect();
+    assert_eq!(
+        brackets,
+        [
+            (Decimal::ZERO, Decimal::new(5, 2)),
+            (Decimal::new(40_000, 0), Decimal::new(15, 2))
+        ]
+    );
+
+    let health = category_named(&saved, "Global Health");
+    assert_eq!(health.list_id, saved.id);
+    assert_eq!(health.multiplier, Decimal::new(15, 1));
+    assert_eq!(eins(health), [111, 222]);
+    let overridden = health
+        .entries
+        .iter()
+        .find(|entry| entry.ein == 222)
+        .unwrap();
+    assert_eq!(overridden.category_id, health.id);
+    assert_eq!(overridden.value_override, Some(Decimal::new(250, 0)));
+    assert!(!overridden.enabled);
+
+    let animals = category_named(&saved, "Animal Welfare");
+    assert_eq!(animals.percent_override, Some(Decimal::new(25, 2)));
+    assert_eq!(eins(animals), [333]);
+
+    assert_eq!(List::from_id(&db, saved.id).await, Some(saved));
+}
+
+#[actix_web::test]
+async fn save_updates_kept_rows_and_deletes_missing_ones() {
+    let db = database().await;
+    let mut list = saved_list(&db, "user_a").await;
+    let health_id = category_named(&list, "Global Health").id;
+
+    list.categories
+        .retain(|category| category.name == "Global Health");
+    let health = &mut list.categories[0];
+    health.entries.retain(|entry| entry.ein == 111);
+    health.entries.push(entry(444));
+    health.value_override = Some(Decimal::new(1_000, 0));
+    list.budget.brackets.truncate(1);
+
+    let saved = list.save(&db, "user_a").await.unwrap();
+
+    assert_eq!(saved.version, 3);
+    assert_eq!(saved.budget.brackets.len(), 1);
+    assert_eq!(saved.categories.len(), 1);
+    let health = &saved.categories[0];
+    assert_eq!(health.id, health_id);
+    assert_eq!(health.value_override, Some(Decimal::new(1_000, 0)));
+    assert_eq!(eins(health), [111, 444]);
+
+    assert_eq!(category::Entity::find().count(&db).await.unwrap(), 1);
+    assert_eq!(entry::Entity::find().count(&db).await.unwrap(), 2);
+    assert_eq!(giving_bracket::Entity::find().count(&db).await.unwrap(), 1);
+}
+
+#[actix_web::test]
+async fn save_refuses_stale_versions() {
+    let db = database().await;
+    let list = List::init_list(&db, "user_a".to_owned()).await.unwrap();
+
+    let mut first = list.clone();
+    first.categories.push(category("Culture", vec![]));
+    first.save(&db, "user_a").await.unwrap();
+
+    let mut second = list;
+    second.categories.push(category("Justice", vec![]));
+    match second.save(&db, "user_a").await {
+        Err(SaveError::Conflict(stored)) => {
+            assert_eq!(stored.version, 2);
+            assert_eq!(stored.categories[0].name, "Culture");
+        }
+        other => panic!("expected a conflict, got {other:?}"),
+    }
+}
+
+#[actix_web::test]
+async fn deleting_a_list_cascades_to_its_rows() {
+    let db = database().await;
+    let list = saved_list(&db, "user_a").await;
+    let other = saved_list(&db, "user_b").await;
+    let scenario = list
+        .fork_scenario(&db, "Plan B".to_owned(), "user_a")
+        .await
+        .unwrap();
+
+    list::Entity::delete_by_id(list.id).exec(&db).await.unwrap();
+
+    assert_eq!(List::from_id(&db, list.id).await, None);
+    assert_eq!(List::from_id(&db, scenario.id).await, None);
+    assert_eq!(list::Entity::find().count(&db).await.unwrap(), 1);
+    assert_eq!(budget::Entity::find().count(&db).await.unwrap(), 1);
+    assert_eq!(giving_bracket::Entity::find().count(&db).await.unwrap(), 2);
+    assert_eq!(category::Entity::find().count(&db).await.unwrap(), 2);
+    assert_eq!(entry::Entity::find().count(&db).await.unwrap(), 3);
+    assert!(list_event::Entity::find()
+        .all(&db)
+        .await
+        .unwrap()
+        .iter()
+        .all(|event| event.list_id == other.id));
+    assert_eq!(List::from_id(&db, other.id).await, Some(other));
+}
+
+#[test]
+fn converts_between_lists_and_models() {
+    let mut health = category("Global Health", vec![entry(111)]);
+    health.id = 3;
+    health.list_id = 1;
+    health.version = 2;
+    health.percent_override = Some(Decimal::new(30, 2));
+    health.entries[0].id = 4;
+    health.entries[0].category_id = 3;
+    health.entries[0].value_override = Some(Decimal::new(100, 0));
+
+    let list = List {
+        id: 1,
+        user_id: "user_a".to_owned(),
+        scenario_of: Some(7),
+        name: Some("Plan B".to_owned()),
+        version: 2,
+        budget: Budget {
+            id: 2,
+            list_id: 1,
+            total_value: Decimal::new(80_000, 0),
+            donation_percent: Decimal::new(12, 2),
+            value_override: None,
+            mode: BudgetMode::Brackets,
+            income_basis: IncomeBasis::Net,
+            net_value: Some(Decimal::new(60_000, 0)),
+            brackets: vec![GivingBracket {
+                id: 5,
+                budget_id: 2,
+                ..bracket(10_000, 8)
+            }],
+            version: 1,
+        },
+        categories: vec![health],
+    };
+
+    let list_model = list::Model::from(&list);
+    assert_eq!(list_model.scenario_of, Some(7));
+    assert_eq!(list_model.name.as_deref(), Some("Plan B"));
+
+    let budget_model = budget::Model::from(&list.budget);
+    assert_eq!(budget_model.mode, budget::BudgetMode::Brackets);
+    assert_eq!(budget_model.income_basis, budget::IncomeBasis::Net);
+    let brackets = list
+        .budget
+        .brackets
+        .iter()
+        .map(|bracket| giving_bracket::Model::from(bracket).into())
+        .collect();
+
+    let categories = list
+        .categories
+        .iter()
+        .map(|category| {
+            let entries = category
+                .entries
+                .iter()
+                .map(|entry| entry::Model::from(entry).into())
+                .collect();
+            (category::Model::from(category), entries).into()
+        })
+        .collect();
+
+    let rebuilt: List = (
+        list_model,
+        Budget::from((budget_model, brackets)),
+        categories,
+    )
+        .into();
+    assert_eq!(rebuilt, list);
+}
+
+#[actix_web::test]
+async fn promoting_a_scenario_replaces_the_live_list_and_deletes_the_scenario() {
+    let db = database().await;
+    let list = saved_list(&db, "user_a").await;
+    let mut scenario = list
+        .fork_scenario(&db, "Plan B".to_owned(), "user_a")
+        .await
+        .unwrap();
+    scenario
+        .categories
+        .retain(|category| category.name == "Animal Welfare");
+    let scenario = scenario.save(&db, "user_a").await.unwrap();
+
+    // A stale live list version changes neither list.
+    assert!(matches!(
+        list.promote_scenario(&db, &scenario, list.version - 1, "user_a")
+            .await,
+        Err(SaveError::Conflict(_))
+    ));
+    assert_eq!(List::from_id(&db, list.id).await, Some(list.clone()));
+    assert_eq!(
+        List::from_id(&db, scenario.id).await,
+        Some(scenario.clone())
+    );
+
+    let live = list
+        .promote_scenario(&db, &scenario, list.version, "user_a")
+        .await
+        .unwrap();
+
+    assert_eq!(live.id, list.id);
+    assert_eq!(live.categories.len(), 1);
+    assert_eq!(
+        live.categories[0].id,
+        category_named(&list, "Animal Welfare").id
+    );
+    assert_eq!(List::from_id(&db, scenario.id).await, None);
+}
+
+#[actix_web::test]
+async fn a_failed_fork_leaves_no_scenario_behind() {
+    let db = database().await;
+    let mut list = saved_list(&db, "user_a").await;
+    // Only reachable with a list that skipped the unique name index, the copy fails to save.
+    list.categories.push(category("Global Health", vec![]));
+
+    assert!(list
+        .fork_scenario(&db, "Plan B".to_owned(), "user_a")
+        .await
+        .is_err());
+
+    assert_eq!(list::Entity::find().count(&db).await.unwrap(), 1);
+    assert_eq!(budget::Entity::find().count(&db).await.unwrap(), 1);
+}
+
+#[actix_web::test]
+async fn save_can_swap_and_reuse_category_names() {
+    let db = database().await;
+    let mut list = saved_list(&db, "user_a").await;
+    let health_id = category_named(&list, "Global Health").id;
+    let animals_id = category_named(&list, "Animal Welfare").id;
+
+    for category in &mut list.categories {
+        category.name = if category.id == health_id {
+            "Animal Welfare".to_owned()
+        } else {
+            "Global Health".to_owned()
+        };
+    }
+    let mut list = list.save(&db, "user_a").await.unwrap();
+
+    assert_eq!(category_named(&list, "Animal Welfare").id, health_id);
+    assert_eq!(category_named(&list, "Global Health").id, animals_id);
+
+    // A new category takes the name the renamed one gave up.
+    let renamed = list
+        .categories
+        .iter_mut()
+        .find(|category| category.id == health_id)
+        .unwrap();
+    renamed.name = "Climate".to_owned();
+    list.categories
+        .insert(0, category("Animal Welfare", vec![entry(555)]));
+    let list = list.save(&db, "user_a").await.unwrap();
+
+    assert_eq!(category_named(&list, "Climate").id, health_id);
+    assert_eq!(eins(category_named(&list, "Animal Welfare")), [555]);
+}
+
+#[actix_web::test]
+async fn category_names_are_unique_regardless_of_case() {
+    let db = database().await;
+    let mut list = saved_list(&db, "user_a").await;
+
+    list.categories.push(category("GLOBAL HEALTH", vec![]));
+
+    assert!(matches!(
+        list.save(&db, "user_a").await,
+        Err(SaveError::Database(_))
+    ));
+    assert_eq!(category::Entity::find().count(&db).await.unwrap(), 2);
+}
+
+#[actix_web::test]
+async fn migrating_merges_categories_that_differ_in_case() {
+    let config = config(false);
+    let db = init_database(&config).await;
+    migrate(&db, &config, MigrateCommand::Up(Some(14)))
+        .await
+        .unwrap();
+
+    let list = List::init_list(&db, "user_a".to_owned()).await.unwrap();
+    let mut category_ids = Vec::new();
+    for name in ["Health", "health", "HEALTH", "Animals"] {
+        let inserted = category::ActiveModel {
+            id: NotSet,
+            list_id: Set(list.id),
+            name: Set(name.to_owned()),
+            multiplier: Set(Decimal::ONE),
+            percent_override: Set(None),
+            value_override: Set(None),
+            enabled: Set(true),
+            version: Set(1),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        category_ids.push(inserted.id);
+    }
+    // EIN 111 is in all three spellings of Health, the oldest entry is kept.
+    for (category_index, ein, multiplier) in [
+        (0, 111, 1),
+        (1, 111, 2),
+        (1, 222, 1),
+        (2, 111, 3),
+        (2, 333, 1),
+        (3, 111, 4),
+    ] {
+        entry::ActiveModel {
+            id: NotSet,
+            category_id: Set(category_ids[category_index]),
+            ein: Set(ein),
+            multiplier: Set(Decimal::new(multiplier, 0)),
+            percent_override: Set(None),
+            value_override: Set(None),
+            enabled: Set(true),
+            version: Set(1),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+
+    migrate(&db, &config, MigrateCommand::Up(None))
+        .await
+        .unwrap();
+
+    let categories = category::Entity::find()
+        .order_by_asc(category::Column::Id)
+        .all(&db)
+        .await
+        .unwrap();
+    assert_eq!(
+        categories
+            .iter()
+            .map(|category| (category.id, category.name.as_str()))
+            .collect::<Vec<_>>(),
+        [(category_ids[0], "Health"), (category_ids[3], "Animals")]
+    );
+
+    let health_entries = entry::Entity::find()
+        .filter(entry::Column::CategoryId.eq(category_ids[0]))
+        .order_by_asc(entry::Column::Ein)
+        .all(&db)
+        .await
+        .unwrap();
+    assert_eq!(
+        health_entries
+            .iter()
+            .map(|entry| (entry.ein, entry.multiplier))
+            .collect::<Vec<_>>(),
+        [
+            (111, Decimal::ONE),
+            (222, Decimal::ONE),
+            (333, Decimal::ONE)
+        ]
+    );
+    assert_eq!(entry::Entity::find().count(&db).await.unwrap(), 4);
+}
@@ -0,0 +1,495 @@
+use leptos_start::list::transfer::{
+    CategoryExport, ChangeKind, EntryExport, ImportIssue, ImportMode, ListExport, EXPORT_VERSION,
+};
+use leptos_start::list::{Budget, BudgetMode, Category, Entry, GivingBracket, IncomeBasis, List};
+use rust_decimal::Decimal;
+
+mod common;
+
+use common::{bracket, stored_category, stored_entry};
+
+/// A saved list with a bracket budget, an override on each level and an empty category.
+fn list() -> List {
+    List {
+        id: 1,
+        user_id: "user_test".to_owned(),
+        scenario_of: None,
+        name: None,
+        version: 3,
+        budget: Budget {
+            id: 1,
+            list_id: 1,
+            total_value: Decimal::new(120_000, 0),
+            donation_percent: Decimal::new(5, 2),
+            value_override: None,
+            mode: BudgetMode::Brackets,
+            income_basis: IncomeBasis::Net,
+            net_value: Some(Decimal::new(90_000, 0)),
+            brackets: vec![
+                GivingBracket {
+                    id: 1,
+                    budget_id: 1,
+                    ..bracket(0, 2)
+                },
+                GivingBracket {
+                    id: 2,
+                    budget_id: 1,
+                    ..bracket(50_000, 10)
+                },
+            ],
+            version: 1,
+        },
+        categories: vec![
+            stored_category(
+                1,
+                "Health",
+                vec![
+                    stored_entry(1, 1, 123_456_789),
+                    Entry {
+                        multiplier: Decimal::new(25, 1),
+                        ..stored_entry(2, 1, 987_654_321)
+                    },
+                ],
+            ),
+            Category {
+                percent_override: Some(Decimal::new(25, 2)),
+                ..stored_category(
+                    2,
+                    "Animals",
+                    vec![Entry {
+                        value_override: Some(Decimal::new(100, 0)),
+                        ..stored_entry(3, 2, 111_111_111)
+                    }],
+                )
+            },
+            Category {
+                enabled: false,
+                ..stored_category(3, "Later", Vec::new())
+            },
+        ],
+    }
+}
+
+/// The stored fields of a list, without ids and versions, which an import can't carry.
+fn stored(list: &List) -> String {
+    let mut export = ListExport::from(list);
+    export.budget.iter_mut().for_each(|budget| {
+        budget.donation_value = Decimal::ZERO;
+    });
+    for category in &mut export.categories {
+        category.donation_value = Decimal::ZERO;
+        category.donation_percent = Decimal::ZERO;
+        for entry in &mut category.entries {
+            entry.donation_value = Decimal::ZERO;
+            entry.donation_percent = Decimal::ZERO;
+        }
+    }
+    export.to_json().unwrap()
+}
+
+fn messages(data: &str, parse: fn(&str) -> Result<ListExport, Vec<ImportIssue>>) -> Vec<String> {
+    parse(data)
+        .unwrap_err()
+        .into_iter()
+        .map(|issue| format!("{}: {}", issue.location, issue.message))
+        .collect()
+}
+
+const CSV_HEADER: &str = "category,category_multiplier,category_percent_override,category_value_override,category_enabled,ein,multiplier,percent_override,value_override,enabled";
+
+#[test]
+fn json_export_round_trips() {
+    let list = list();
+    let json = ListExport::from(&list).to_json().unwrap();
+    let import = ListExport::from_json(&json).unwrap();
+
+    assert_eq!(import, ListExport::from(&list));
+
+    let imported = list.apply_import(&import, ImportMode::Replace);
+    assert_eq!(imported, list);
+}
+
+#[test]
+fn csv_export_round_trips_without_the_budget() {
+    let list = list();
+    let csv = ListExport::from(&list).to_csv().unwrap();
+    let import = ListExport::from_csv(&csv).unwrap();
+
+    assert_eq!(import.budget, None);
+    assert_eq!(
+        import
+            .categories
+            .iter()
+            .map(|category| (category.name.as_str(), category.entries.len()))
+            .collect::<Vec<_>>(),
+        [("Health", 2), ("Animals", 1), ("Later", 0)]
+    );
+
+    let imported = list.apply_import(&import, ImportMode::Replace);
+    assert_eq!(imported, list);
+    assert_eq!(stored(&imported), stored(&list));
+}
+
+#[test]
+fn imports_into_an_empty_list_as_new_rows() {
+    let list = list();
+    let empty = List {
+        categories: Vec::new(),
+        ..list.clone()
+    };
+
+    let imported = empty.apply_import(&ListExport::from(&list), ImportMode::Merge);
+
+    assert_eq!(stored(&imported), stored(&list));
+    assert!(imported.categories.iter().all(|category| category.id == 0));
+    assert!(imported
+        .categories
+        .iter()
+        .flat_map(|category| &category.entries)
+        .all(|entry| entry.id == 0 && entry.version == 0));
+}
+
+#[test]
+fn rejects_malformed_csv_rows() {
+    let issues = messages(
+        &format!(
+            "{CSV_HEADER}\n\
+             Health,one,,,true,123456789,1,,,true\n\
+             Health,1,,,maybe,123456789,1,,,true\n\
+             Health,1,,,true,12-3,1,,,true\n\
+             Health,1,,,true,123456789,1,0.5%,,true\n"
+        ),
+        ListExport::from_csv,
+    );
+
+    assert_eq!(
+        issues,
+        [
+            "line 2: `category_multiplier` must be a number, got `one`.",
+            "line 3: `category_enabled` must be true or false, got `maybe`.",
+            "line 4: `ein` must be a number, got `12-3`.",
+            "line 5: `percent_override` must be a number, got `0.5%`.",
+        ]
+    );
+
+    let issues = messages("category,ein\nHealth,123456789\n", ListExport::from_csv);
+    assert_eq!(
+        issues,
+        [
+            "header: Missing column `category_multiplier`.",
+            "header: Missing column `category_enabled`.",
+            "header: Missing column `multiplier`.",
+            "header: Missing column `enabled`.",
+        ]
+    );
+}
+
+#[test]
+fn rejects_rows_that_disagree_on_their_category() {
+    let issues = messages(
+        &format!(
+            "{CSV_HEADER}\n\
+             Health,1,,,true,123456789,1,,,true\n\
+             Health,2,,,true,987654321,1,,,true\n"
+        ),
+        ListExport::from_csv,
+    );
+
+    assert_eq!(
+        issues,
+        ["line 3: Category `Health` has different settings than on an earlier row."]
+    );
+}
+
+#[test]
+fn groups_csv_rows_by_category_name_regardless_of_case() {
+    let import = ListExport::from_csv(&format!(
+        "{CSV_HEADER}\n\
+         Health,1,,,true,123456789,1,,,true\n\
+         HEALTH,1,,,true,987654321,1,,,true\n"
+    ))
+    .unwrap();
+
+    assert_eq!(import.categories.len(), 1);
+    assert_eq!(import.categories[0].name, "Health");
+    assert_eq!(
+        import.categories[0]
+            .entries
+            .iter()
+            .map(|entry| entry.ein)
+            .collect::<Vec<_>>(),
+        [123_456_789, 987_654_321]
+    );
+}
+
+#[test]
+fn rejects_duplicate_names_and_eins() {
+    let issues = messages(
+        &format!(
+            "{CSV_HEADER}\n\
+             Health,1,,,true,123456789,1,,,true\n\
+             Health,1,,,true,123456789,2,,,true\n"
+        ),
+        ListExport::from_csv,
+    );
+    assert_eq!(
+        issues,
+        ["category `Health`, EIN 123456789: EIN is listed more than once in the category."]
+    );
+
+    let mut export = ListExport::from(&list());
+    export.categories[1].name = "health".to_owned();
+    export.categories[2].name = " ".to_owned();
+    let issues = messages(&export.to_json().unwrap(), ListExport::from_json);
+
+    assert_eq!(
+        issues,
+        [
+            "category `health`: Category name is used more than once.",
+            "category ` `: Category name must not be empty.",
+        ]
+    );
+}
+
+#[test]
+fn rejects_negative_and_out_of_range_values() {
+    let mut export = ListExport::from(&list());
+    {
+        let budget = export.budget.as_mut().unwrap();
+        budget.total_value = Decimal::new(-1, 0);
+        budget.donation_percent = Decimal::new(15, 1);
+        budget.value_override = Some(Decimal::new(-1, 0));
+        budget.net_value = Some(Decimal::new(-1, 0));
+        budget.brackets[1].threshold = Decimal::ZERO;
+    }
+    export.categories[0].multiplier = Decimal::new(-1, 0);
+    export.categories[0].entries[0].value_override = Some(Decimal::new(-5, 0));
+    export.categories[0].entries[1].ein = 0;
+    export.categories[1].percent_override = Some(Decimal::new(-1, 2));
+
+    let issues = messages(&export.to_json().unwrap(), ListExport::from_json);
+
+    assert_eq!(
+        issues,
+        [
+            "budget: `total_value` must not be negative.",
+            "budget: `donation_percent` must be between 0 and 1.",
+            "budget: `value_override` must not be negative.",
+            "budget: `net_value` must not be negative.",
+            "budget bracket at 0: Bracket threshold is used more than once.",
+            "category `Health`: `multiplier` must not be negative.",
+            "category `Health`, EIN 123456789: `value_override` must not be negative.",
+            "category `Health`, EIN 0: EIN must be a positive 9 digit number.",
+            "category `Animals`: `percent_override` must be between 0 and 1.",
+        ]
+    );
+}
+
+#[test]
+fn validates_saved_lists_like_imports_and_caps_overrides_at_the_budget() {
+    assert_eq!(list().validate(), Ok(()));
+
+    let mut list = list();
+    list.categories[0].value_override = Some(Decimal::new(6_001, 0));
+    list.categories[0].entries[1].multiplier = Decimal::new(-1, 0);
+    list.categories[1].entries[0].value_override = Some(Decimal::new(7_000, 0));
+
+    let issues: Vec<String> = list
+        .validate()
+        .unwrap_err()
+        .iter()
+        .map(|issue| format!("{}: {}", issue.location, issue.message))
+        .collect();
+
+    assert_eq!(
+        issues,
+        [
+            "category `Health`, EIN 987654321: `multiplier` must not be negative.",
+            "category `Health`: `value_override` must not be larger than the budget.",
+            "category `Animals`, EIN 111111111: `value_override` must not be larger than the budget.",
+        ]
+    );
+}
+
+#[test]
+fn rejects_other_export_versions() {
+    let mut export = ListExport::from(&list());
+    export.version = EXPORT_VERSION + 1;
+
+    let issues = messages(&export.to_json().unwrap(), ListExport::from_json);
+
+    assert_eq!(
+        issues,
+        [format!(
+            "version: Unsupported export version {}, expected {EXPORT_VERSION}.",
+            EXPORT_VERSION + 1
+        )]
+    );
+}
+
+/// Every change the preview lists is in the applied list, and nothing else is.
+fn check_preview(list: &List, import: &ListExport, mode: ImportMode) {
+    let result = list.apply_import(import, mode);
+    let preview = list.preview_changes(&result, mode);
+
+    let find = |list: &List, name: &str| {
+        list.categories
+            .iter()
+            .find(|category| category.name.eq_ignore_ascii_case(name))
+            .cloned()
+    };
+
+    for change in &preview.categories {
+        let before = find(list, &change.name);
+        let after = find(&result, &change.name);
+
+        match change.kind {
+            ChangeKind::Added => assert!(before.is_none() && after.is_some(), "{change:?}"),
+            ChangeKind::Removed => assert!(before.is_some() && after.is_none(), "{change:?}"),
+            ChangeKind::Unchanged => {
+                assert_eq!(
+                    stored(&List {
+                        categories: vec![before.unwrap()],
+                        ..list.clone()
+                    }),
+                    stored(&List {
+                        categories: vec![after.unwrap()],
+                        ..list.clone()
+                    }),
+                    "{change:?}"
+                );
+            }
+            ChangeKind::Modified => {
+                let after = after.unwrap();
+                for field in &change.fields {
+                    let value = match field.field.as_str() {
+                        "multiplier" => after.multiplier.to_string(),
+                        "percent_override" => after
+                            .percent_override
+                            .map(|value| value.to_string())
+                            .unwrap_or_default(),
+                        "value_override" => after
+                            .value_override
+                            .map(|value| value.to_string())
+                            .unwrap_or_default(),
+                        "enabled" => after.enabled.to_string(),
+                        field => panic!("unexpected field {field}"),
+                    };
+                    assert_eq!(field.after, value, "{change:?}");
+                }
+                for entry in &change.entries {
+                    let applied = after.entries.iter().any(|after| after.ein == entry.ein);
+                    assert_eq!(applied, entry.kind != ChangeKind::Removed, "{entry:?}");
+                }
+            }
+        }
+    }
+
+    let previewed: Vec<String> = preview
+        .categories
+        .iter()
+        .filter(|change| change.kind != ChangeKind::Removed)
+        .map(|change| change.name.to_lowercase())
+        .collect();
+    let mut applied: Vec<String> = result
+        .categories
+        .iter()
+        .map(|category| category.name.to_lowercase())
+        .collect();
+    applied.sort();
+    let mut sorted = previewed.clone();
+    sorted.sort();
+    assert_eq!(sorted, applied);
+
+    // Applying the same import again changes nothing.
+    let again = result.preview_changes(&result.apply_import(import, mode), mode);
+    assert!(!again.has_changes(), "{again:?}");
+}
+
+#[test]
+fn preview_matches_the_applied_import() {
+    let list = list();
+
+    let mut export = ListExport::from(&list);
+    export.budget.as_mut().unwrap().donation_percent = Decimal::new(10, 2);
+    export.categories.remove(2);
+    export.categories[0].entries.remove(0);
+    export.categories[0].entries[0].enabled = false;
+    let added = EntryExport {
+        ein: 222_222_222,
+        ..export.categories[0].entries[0].clone()
+    };
+    export.categories[0].entries.push(added);
+    export.categories[1].name = "ANIMALS".to_owned();
+    export.categories[1].percent_override = None;
+    let added = CategoryExport {
+        name: "Education".to_owned(),
+        ..export.categories[1].clone()
+    };
+    export.categories.push(added);
+
+    for mode in [ImportMode::Replace, ImportMode::Merge] {
+        check_preview(&list, &export, mode);
+    }
+
+    let result = list.apply_import(&export, ImportMode::Replace);
+    let preview = list.preview_changes(&result, ImportMode::Replace);
+
+    assert_eq!(preview.budget.len(), 1);
+    assert_eq!(preview.budget[0].field, "donation_percent");
+    assert_eq!(
+        (
+            preview.budget[0].before.as_str(),
+            preview.budget[0].after.as_str()
+        ),
+        ("0.05", "0.10")
+    );
+    assert_eq!(
+        preview
+            .categories
+            .iter()
+            .map(|change| (change.name.as_str(), change.kind))
+            .collect::<Vec<_>>(),
+        [
+            ("Health", ChangeKind::Modified),
+            ("ANIMALS", ChangeKind::Modified),
+            ("Education", ChangeKind::Added),
+            ("Later", ChangeKind::Removed),
+        ]
+    );
+    assert_eq!(
+        preview.categories[0]
+            .entries
+            .iter()
+            .map(|change| (change.ein, change.kind))
+            .collect::<Vec<_>>(),
+        [
+            (987_654_321, ChangeKind::Modified),
+            (222_222_222, ChangeKind::Added),
+            (123_456_789, ChangeKind::Removed),
+        ]
+    );
+
+    // Merging keeps what the import leaves out.
+    let merged = list.apply_import(&export, ImportMode::Merge);
+    let preview = list.preview_changes(&merged, ImportMode::Merge);
+    assert!(preview
+        .categories
+        .iter()
+        .all(|change| change.kind != ChangeKind::Removed));
+    assert!(merged
+        .categories
+        .iter()
+        .any(|category| category.name == "Later"));
+}
+
+#[test]
+fn unchanged_import_previews_no_changes() {
+    let list = list();
+    let export = ListExport::from(&list);
+
+    for mode in [ImportMode::Replace, ImportMode::Merge] {
+        let preview = list.preview_changes(&list.apply_import(&export, mode), mode);
+        assert!(!preview.has_changes(), "{preview:?}");
+    }
+}
@@ -0,0 +1,87 @@
+//! The Postgres run is ignored unless asked for, it gets a schema of its own:
+//! `DATABASE_URL=postgres://... cargo test --features sqlite --test migrations -- --ignored`
+
+use leptos_start::config::{DatabaseBackend, DatabaseConfig};
+use leptos_start::database::{connect_database, migrate, migration_status, MigrateCommand};
+use leptos_start::entity::{color, default_category};
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, EntityTrait, PaginatorTrait};
+use sea_orm_migration::MigrationStatus;
+
+mod common;
+
+use common::db::config;
+
+async fn statuses(db: &DatabaseConnection, config: &DatabaseConfig) -> Vec<MigrationStatus> {
+    migration_status(db, config)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(_, status)| status)
+        .collect()
+}
+
+async fn assert_all(db: &DatabaseConnection, config: &DatabaseConfig, status: MigrationStatus) {
+    let statuses = statuses(db, config).await;
+    assert!(!statuses.is_empty());
+    assert!(
+        statuses.iter().all(|applied| *applied == status),
+        "{statuses:?}"
+    );
+}
+
+async fn assert_seeded(db: &DatabaseConnection) {
+    assert!(default_category::Entity::find().count(db).await.unwrap() > 0);
+    assert!(color::Entity::find().count(db).await.unwrap() > 0);
+}
+
+/// Applies every migration, rolls them all back, applies them again and starts over fresh.
+async fn migrates_up_down_and_fresh(config: &DatabaseConfig) {
+    let db = connect_database(config).await;
+
+    migrate(&db, config, MigrateCommand::Up(None))
+        .await
+        .unwrap();
+    assert_all(&db, config, MigrationStatus::Applied).await;
+    assert_seeded(&db).await;
+
+    migrate(&db, config, MigrateCommand::Down(None))
+        .await
+        .unwrap();
+    assert_all(&db, config, MigrationStatus::Pending).await;
+
+    migrate(&db, config, MigrateCommand::Up(None))
+        .await
+        .unwrap();
+    assert_all(&db, config, MigrationStatus::Applied).await;
+
+    migrate(&db, config, MigrateCommand::Fresh).await.unwrap();
+    assert_all(&db, config, MigrationStatus::Applied).await;
+    assert_seeded(&db).await;
+}
+
+#[actix_web::test]
+async fn migrates_sqlite_up_down_and_fresh() {
+    migrates_up_down_and_fresh(&config(false)).await;
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn migrates_postgres_up_down_and_fresh() {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must point at Postgres.");
+    let schema = "migrates_up_down_and_fresh";
+
+    Database::connect(&url)
+        .await
+        .unwrap()
+        .execute_unprepared(&format!(r#"DROP SCHEMA IF EXISTS "{schema}" CASCADE"#))
+        .await
+        .unwrap();
+
+    migrates_up_down_and_fresh(&DatabaseConfig {
+        backend: DatabaseBackend::Postgres,
+        url,
+        schema_name: schema.to_owned(),
+        ..config(false)
+    })
+    .await;
+}
@@ -0,0 +1,569 @@
+use leptos_start::list::allocation::{Allocation, CategoryAllocation, EntryAllocation};
+use leptos_start::list::transfer::ListExport;
+use leptos_start::list::{Budget, BudgetMode, Category, Entry, GivingBracket, IncomeBasis, List};
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+mod common;
+
+use common::{stored_category, stored_entry};
+
+/// A list as written in `tests/golden`, with the processed values it must come out with.
+/// Leaving out a multiplier means 1, leaving out `enabled` means true.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Golden {
+    budget: GoldenBudget,
+    #[serde(default)]
+    categories: Vec<GoldenCategory>,
+    expected: Expected,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GoldenBudget {
+    total_value: Decimal,
+    #[serde(default)]
+    donation_percent: Decimal,
+    value_override: Option<Decimal>,
+    #[serde(default)]
+    mode: BudgetMode,
+    #[serde(default)]
+    income_basis: IncomeBasis,
+    net_value: Option<Decimal>,
+    /// Threshold and percent.
+    #[serde(default)]
+    brackets: Vec<(Decimal, Decimal)>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GoldenCategory {
+    name: String,
+    multiplier: Option<Decimal>,
+    percent_override: Option<Decimal>,
+    value_override: Option<Decimal>,
+    enabled: Option<bool>,
+    #[serde(default)]
+    entries: Vec<GoldenEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GoldenEntry {
+    ein: i32,
+    multiplier: Option<Decimal>,
+    percent_override: Option<Decimal>,
+    value_override: Option<Decimal>,
+    enabled: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Expected {
+    budget: Decimal,
+    categories: Vec<ExpectedCategory>,
+}
+
+/// Value and percent of the category, then of each of its entries.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExpectedCategory {
+    value: Decimal,
+    percent: Decimal,
+    #[serde(default)]
+    entries: Vec<(Decimal, Decimal)>,
+}
+
+fn budget() -> Budget {
+    Budget {
+        id: 1,
+        list_id: 1,
+        total_value: Decimal::ZERO,
+        donation_percent: Decimal::ZERO,
+        value_override: None,
+        mode: BudgetMode::Percent,
+        income_basis: IncomeBasis::Gross,
+        net_value: None,
+        brackets: Vec::new(),
+        version: 1,
+    }
+}
+
+impl From<GoldenBudget> for Budget {
+    fn from(golden: GoldenBudget) -> Self {
+        Budget {
+            total_value: golden.total_value,
+            donation_percent: golden.donation_percent,
+            value_override: golden.value_override,
+            mode: golden.mode,
+            income_basis: golden.income_basis,
+            net_value: golden.net_value,
+            brackets: golden
+                .brackets
+                .into_iter()
+                .enumerate()
+                .map(|(index, (threshold, percent))| GivingBracket {
+                    id: index as i32 + 1,
+                    budget_id: 1,
+                    threshold,
+                    percent,
+                })
+                .collect(),
+            ..budget()
+        }
+    }
+}
+
+fn list(budget: Budget, categories: Vec<Category>) -> List {
+    List {
+        id: 1,
+        user_id: "user_test".to_owned(),
+        scenario_of: None,
+        name: None,
+        version: 1,
+        budget,
+        categories,
+    }
+}
+
+impl Golden {
+    fn list(self) -> (List, Expected) {
+        let mut entry_id = 0;
+        let categories = self
+            .categories
+            .into_iter()
+            .enumerate()
+            .map(|(index, golden)| {
+                let id = index as i32 + 1;
+                let entries = golden
+                    .entries
+                    .into_iter()
+                    .map(|golden| {
+                        entry_id += 1;
+                        Entry {
+                            multiplier: golden.multiplier.unwrap_or(Decimal::ONE),
+                            percent_override: golden.percent_override,
+                            value_override: golden.value_override,
+                            enabled: golden.enabled.unwrap_or(true),
+                            ..stored_entry(entry_id, id, golden.ein)
+                        }
+                    })
+                    .collect();
+
+                Category {
+                    multiplier: golden.multiplier.unwrap_or(Decimal::ONE),
+                    percent_override: golden.percent_override,
+                    value_override: golden.value_override,
+                    enabled: golden.enabled.unwrap_or(true),
+                    ..stored_category(id, &golden.name, entries)
+                }
+            })
+            .collect();
+
+        (list(self.budget.into(), categories), self.expected)
+    }
+}
+
+#[test]
+fn matches_golden_lists() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|file| file.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let golden: Golden = serde_json::from_str(&fs::read_to_string(&path).unwrap())
+            .unwrap_or_else(|error| panic!("{name}: {error}"));
+        let (list, expected) = golden.list();
+
+        let allocation = list.process_list();
+
+        assert_eq!(allocation.budget.donation_value, expected.budget, "{name}");
+        assert_eq!(
+            list.categories.len(),
+            expected.categories.len(),
+            "{name}: categories"
+        );
+        for (position, (category, expected)) in
+            list.categories.iter().zip(&expected.categories).enumerate()
+        {
+            let at = format!("{name}: {}", category.name);
+            let allocated = allocation.category(position, category).unwrap();
+            assert_eq!(allocated.donation_value, expected.value, "{at}");
+            assert_eq!(allocated.donation_percent, expected.percent, "{at}");
+
+            let entries: Vec<(Decimal, Decimal)> = category
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(position, entry)| allocated.entry(position, entry).unwrap())
+                .map(|entry| (entry.donation_value, entry.donation_percent))
+                .collect();
+            assert_eq!(entries, expected.entries, "{at}: entries");
+        }
+
+        check_allocation(&list, &allocation);
+    }
+}
+
+fn is_overridden(category: &Category) -> bool {
+    category.value_override.is_some() || category.percent_override.is_some()
+}
+
+fn is_entry_overridden(entry: &Entry) -> bool {
+    entry.value_override.is_some() || entry.percent_override.is_some()
+}
+
+/// The properties every processed list has, whatever its input.
+fn check_allocation(list: &List, allocation: &Allocation) {
+    let budget_value = allocation.budget.donation_value;
+    assert!(budget_value >= Decimal::ZERO, "negative budget");
+    assert_eq!(allocation.categories.len(), list.categories.len());
+
+    let allocated: Vec<(&Category, &CategoryAllocation)> = list
+        .categories
+        .iter()
+        .enumerate()
+        .map(|(position, category)| (category, allocation.category(position, category).unwrap()))
+        .collect();
+    let included: Vec<(&Category, &CategoryAllocation)> = allocated
+        .iter()
+        .copied()
+        .filter(|(_, processed)| processed.included)
+        .collect();
+
+    for (category, processed) in allocated {
+        assert!(
+            processed.donation_value >= Decimal::ZERO,
+            "{category:?} {processed:?}"
+        );
+        assert!(
+            processed.donation_percent >= Decimal::ZERO,
+            "{category:?} {processed:?}"
+        );
+        assert_eq!(
+            processed.included,
+            category.enabled && !category.entries.is_empty(),
+            "{category:?}"
+        );
+
+        if !processed.included {
+            assert!(processed.donation_value.is_zero(), "{category:?}");
+            assert!(processed.donation_percent.is_zero(), "{category:?}");
+        } else if let Some(value_override) = category.value_override {
+            assert_eq!(processed.donation_value, value_override, "{category:?}");
+        } else if let Some(percent_override) = category.percent_override {
+            assert_eq!(processed.donation_percent, percent_override);
+            assert_eq!(
+                processed.donation_value,
+                (percent_override * budget_value).round_dp(2)
+            );
+        }
+
+        check_entries(category, processed);
+    }
+
+    let shared: Vec<&CategoryAllocation> = included
+        .iter()
+        .filter(|(category, _)| !is_overridden(category))
+        .map(|(_, processed)| *processed)
+        .collect();
+    if shared.is_empty() {
+        return;
+    }
+
+    let overridden_value: Decimal = included
+        .iter()
+        .filter(|(category, _)| is_overridden(category))
+        .map(|(_, processed)| processed.donation_value)
+        .sum();
+    let overridden_percent: Decimal = included
+        .iter()
+        .filter(|(category, _)| is_overridden(category))
+        .map(|(_, processed)| processed.donation_percent)
+        .sum();
+
+    let value: Decimal = included
+        .iter()
+        .map(|(_, processed)| processed.donation_value)
+        .sum();
+    if overridden_value <= budget_value {
+        assert_eq!(
+            value, budget_value,
+            "category values must add up to the budget"
+        );
+    } else {
+        assert!(shared
+            .iter()
+            .all(|processed| processed.donation_value.is_zero()));
+    }
+
+    let percent: Decimal = included
+        .iter()
+        .map(|(_, processed)| processed.donation_percent)
+        .sum();
+    if overridden_percent <= Decimal::ONE {
+        assert_eq!(
+            percent,
+            Decimal::ONE,
+            "category percents must add up to 100%"
+        );
+    }
+}
+
+fn check_entries(category: &Category, processed: &CategoryAllocation) {
+    let category_value = processed.donation_value;
+    assert_eq!(processed.entries.len(), category.entries.len());
+
+    let allocated: Vec<(&Entry, &EntryAllocation)> = category
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(position, entry)| (entry, processed.entry(position, entry).unwrap()))
+        .collect();
+    let counted: Vec<(&Entry, &EntryAllocation)> = allocated
+        .iter()
+        .copied()
+        .filter(|(entry, _)| entry.enabled && processed.included)
+        .collect();
+
+    for (entry, processed_entry) in allocated {
+        assert!(processed_entry.donation_value >= Decimal::ZERO, "{entry:?}");
+        assert!(
+            processed_entry.donation_percent >= Decimal::ZERO,
+            "{entry:?}"
+        );
+
+        if !entry.enabled || !processed.included {
+            assert!(processed_entry.donation_value.is_zero(), "{entry:?}");
+            assert!(processed_entry.donation_percent.is_zero(), "{entry:?}");
+        } else if let Some(value_override) = entry.value_override {
+            assert_eq!(processed_entry.donation_value, value_override, "{entry:?}");
+        } else if let Some(percent_override) = entry.percent_override {
+            assert_eq!(processed_entry.donation_percent, percent_override);
+            assert_eq!(
+                processed_entry.donation_value,
+                (percent_override * category_value).round_dp(2)
+            );
+        }
+    }
+
+    assert_eq!(
+        processed.has_entry_overrides,
+        counted.iter().any(|(entry, _)| is_entry_overridden(entry)),
+        "{category:?}"
+    );
+    if counted.iter().all(|(entry, _)| is_entry_overridden(entry)) {
+        return;
+    }
+
+    let overridden_value: Decimal = counted
+        .iter()
+        .filter(|(entry, _)| is_entry_overridden(entry))
+        .map(|(_, processed)| processed.donation_value)
+        .sum();
+    let overridden_percent: Decimal = counted
+        .iter()
+        .filter(|(entry, _)| is_entry_overridden(entry))
+        .map(|(_, processed)| processed.donation_percent)
+        .sum();
+
+    let value: Decimal = counted
+        .iter()
+        .map(|(_, processed)| processed.donation_value)
+        .sum();
+    if overridden_value <= category_value {
+        assert_eq!(
+            value, category_value,
+            "entry values must add up to {category:?}"
+        );
+    }
+
+    let percent: Decimal = counted
+        .iter()
+        .map(|(_, processed)| processed.donation_percent)
+        .sum();
+    if overridden_percent <= Decimal::ONE {
+        assert_eq!(percent, Decimal::ONE, "entry percents must add up to 100%");
+    }
+}
+
+fn cents(max: i64) -> impl Strategy<Value = Decimal> {
+    (0..=max).prop_map(|cents| Decimal::new(cents, 2))
+}
+
+/// A value override, a percent override or neither.
+fn overrides(max_value: i64) -> impl Strategy<Value = (Option<Decimal>, Option<Decimal>)> {
+    prop_oneof![
+        6 => Just((None, None)),
+        2 => cents(max_value).prop_map(|value| (Some(value), None)),
+        2 => (0..=100i64).prop_map(|percent| (None, Some(Decimal::new(percent, 2)))),
+    ]
+}
+
+fn budgets() -> impl Strategy<Value = Budget> {
+    (
+        cents(50_000_000),
+        0..=100i64,
+        prop::option::weighted(0.2, cents(1_000_000)),
+        prop_oneof![Just(BudgetMode::Percent), Just(BudgetMode::Brackets)],
+        prop_oneof![Just(IncomeBasis::Gross), Just(IncomeBasis::Net)],
+        prop::option::of(cents(50_000_000)),
+        prop::collection::vec((cents(50_000_000), 0..=100i64), 0..4),
+    )
+        .prop_map(
+            |(total_value, percent, value_override, mode, income_basis, net_value, brackets)| {
+                Budget {
+                    total_value,
+                    donation_percent: Decimal::new(percent, 2),
+                    value_override,
+                    mode,
+                    income_basis,
+                    net_value,
+                    brackets: brackets
+                        .into_iter()
+                        .map(|(threshold, percent)| GivingBracket {
+                            id: 0,
+                            budget_id: 1,
+                            threshold,
+                            percent: Decimal::new(percent, 2),
+                        })
+                        .collect(),
+                    ..budget()
+                }
+            },
+        )
+}
+
+fn entries() -> impl Strategy<Value = Entry> {
+    (
+        1..1_000_000i32,
+        0..=500i64,
+        overrides(100_000),
+        prop::bool::weighted(0.85),
+        prop::bool::weighted(0.7),
+    )
+        .prop_map(
+            |(ein, multiplier, (value_override, percent_override), enabled, saved)| Entry {
+                multiplier: Decimal::new(multiplier, 2),
+                value_override,
+                percent_override,
+                enabled,
+                // Saved rows get an id of their own in `lists`.
+                ..stored_entry(i32::from(saved), 0, ein)
+            },
+        )
+}
+
+fn categories() -> impl Strategy<Value = Category> {
+    (
+        0..=500i64,
+        overrides(500_000),
+        prop::bool::weighted(0.85),
+        prop::collection::vec(entries(), 0..6),
+        prop::bool::weighted(0.7),
+    )
+        .prop_map(
+            |(multiplier, (value_override, percent_override), enabled, entries, saved)| Category {
+                multiplier: Decimal::new(multiplier, 2),
+                value_override,
+                percent_override,
+                enabled,
+                ..stored_category(i32::from(saved), "Generated", entries)
+            },
+        )
+}
+
+/// Lists being edited: saved rows have an id of their own, rows added since have id 0.
+fn lists() -> impl Strategy<Value = List> {
+    (budgets(), prop::collection::vec(categories(), 0..8)).prop_map(|(budget, mut categories)| {
+        let mut entry_id = 0;
+        for (index, category) in categories.iter_mut().enumerate() {
+            if category.id != 0 {
+                category.id = index as i32 + 1;
+            }
+            for entry in &mut category.entries {
+                // Entries of a new category are new as well.
+                if entry.id != 0 && category.id != 0 {
+                    entry_id += 1;
+                    entry.id = entry_id;
+                } else {
+                    entry.id = 0;
+                }
+                entry.category_id = category.id;
+            }
+        }
+        list(budget, categories)
+    })
+}
+
+#[test]
+fn unsaved_rows_are_allocated_under_temporary_keys() {
+    let budget = Budget {
+        total_value: Decimal::new(1_000, 0),
+        donation_percent: Decimal::new(10, 2),
+        ..budget()
+    };
+    let saved = stored_category(
+        1,
+        "Saved",
+        vec![stored_entry(1, 1, 111), stored_entry(0, 1, 222)],
+    );
+    let unsaved = stored_category(0, "Unsaved", vec![stored_entry(0, 0, 333)]);
+
+    let list = list(budget, vec![saved, unsaved]);
+
+    let allocation = list.process_list();
+
+    assert_eq!(allocation.budget.donation_value, Decimal::new(100, 0));
+    assert_eq!(allocation.allocated_value(), Decimal::new(100, 0));
+    assert_eq!(list.planned_giving(), Decimal::new(100, 0));
+    assert_eq!(allocation.categories.keys().collect::<Vec<_>>(), [&-2, &1]);
+
+    let saved = &allocation.categories[&1];
+    assert_eq!(saved.donation_value, Decimal::new(50, 0));
+    assert_eq!(saved.entries.keys().collect::<Vec<_>>(), [&-2, &1]);
+    assert_eq!(saved.entries[&1].donation_value, Decimal::new(25, 0));
+    assert_eq!(saved.entries[&-2].donation_value, Decimal::new(25, 0));
+
+    let unsaved = allocation.category(1, &list.categories[1]).unwrap();
+    assert_eq!(unsaved.donation_value, Decimal::new(50, 0));
+    assert_eq!(unsaved.entries[&-1].donation_value, Decimal::new(50, 0));
+
+    let export = ListExport::from(&list);
+    assert_eq!(export.categories[1].donation_value, Decimal::new(50, 0));
+    assert_eq!(
+        export.categories[1].entries[0].donation_value,
+        Decimal::new(50, 0)
+    );
+}
+
+proptest! {
+    #[test]
+    fn allocations_hold_for_any_list(list in lists()) {
+        let allocation = list.process_list();
+
+        check_allocation(&list, &allocation);
+    }
+
+    #[test]
+    fn processing_is_pure(list in lists()) {
+        let before = list.clone();
+        let once = list.process_list();
+        let twice = list.process_list();
+
+        prop_assert_eq!(once, twice);
+        prop_assert_eq!(list, before);
+    }
+}
@@ -0,0 +1,113 @@
+use actix_web::http::Method;
+use leptos_start::clerk_middleware::routes::{Access, RouteMatcher};
+
+fn get(routes: &RouteMatcher, path: &str) -> Access {
+    routes.access(&Method::GET, path)
+}
+
+#[test]
+fn protects_everything_by_default() {
+    let routes = RouteMatcher::default();
+
+    assert_eq!(get(&routes, "/"), Access::Protected);
+    assert_eq!(get(&routes, "/api/get_list"), Access::Protected);
+
+    let routes = RouteMatcher::public_by_default().protect("/api/**");
+
+    assert_eq!(get(&routes, "/"), Access::Public);
+    assert_eq!(get(&routes, "/engine"), Access::Public);
+    assert_eq!(get(&routes, "/api/get_list"), Access::Protected);
+}
+
+#[test]
+fn matches_exact_paths_segment_by_segment() {
+    let routes = RouteMatcher::default().public("/api/is_logged_in");
+
+    assert_eq!(get(&routes, "/api/is_logged_in"), Access::Public);
+    assert_eq!(get(&routes, "/api/is_logged_in_as"), Access::Protected);
+    assert_eq!(get(&routes, "/api/is_logged_in/extra"), Access::Protected);
+    assert_eq!(get(&routes, "/api"), Access::Protected);
+    assert_eq!(get(&routes, "/other/api/is_logged_in"), Access::Protected);
+}
+
+#[test]
+fn ignores_trailing_and_repeated_slashes() {
+    let routes = RouteMatcher::default().public("/healthz/");
+
+    assert_eq!(get(&routes, "/healthz"), Access::Public);
+    assert_eq!(get(&routes, "/healthz/"), Access::Public);
+    assert_eq!(get(&routes, "//healthz"), Access::Public);
+}
+
+#[test]
+fn star_matches_within_one_segment() {
+    let routes = RouteMatcher::default()
+        .public("/api/get_scenario*")
+        .public("/assets/*.css")
+        .public("/pkg/*_bg*.wasm");
+
+    assert_eq!(get(&routes, "/api/get_scenario"), Access::Public);
+    assert_eq!(get(&routes, "/api/get_scenarios"), Access::Public);
+    assert_eq!(get(&routes, "/api/get_scenario/1"), Access::Protected);
+    assert_eq!(get(&routes, "/api/get_list"), Access::Protected);
+
+    assert_eq!(get(&routes, "/assets/main.css"), Access::Public);
+    assert_eq!(get(&routes, "/assets/.css"), Access::Public);
+    assert_eq!(get(&routes, "/assets/main.css.map"), Access::Protected);
+    assert_eq!(get(&routes, "/assets/css/main.css"), Access::Protected);
+
+    assert_eq!(get(&routes, "/pkg/leptos_start_bg.wasm"), Access::Public);
+    assert_eq!(get(&routes, "/pkg/leptos_start.wasm"), Access::Protected);
+}
+
+#[test]
+fn double_star_matches_any_number_of_segments() {
+    let routes = RouteMatcher::default()
+        .public("/pkg/**")
+        .public("/docs/**/index.html");
+
+    assert_eq!(get(&routes, "/pkg"), Access::Public);
+    assert_eq!(get(&routes, "/pkg/leptos_start.js"), Access::Public);
+    assert_eq!(get(&routes, "/pkg/snippets/a/b.js"), Access::Public);
+    assert_eq!(get(&routes, "/pkgs/leptos_start.js"), Access::Protected);
+
+    assert_eq!(get(&routes, "/docs/index.html"), Access::Public);
+    assert_eq!(get(&routes, "/docs/a/b/index.html"), Access::Public);
+    assert_eq!(get(&routes, "/docs/a/b/other.html"), Access::Protected);
+}
+
+#[test]
+fn first_matching_rule_wins() {
+    let routes = RouteMatcher::public_by_default()
+        .public("/api/get_auth_provider*")
+        .protect("/api/**");
+
+    assert_eq!(get(&routes, "/api/get_auth_provider"), Access::Public);
+    assert_eq!(get(&routes, "/api/save_list"), Access::Protected);
+
+    // The broader rule first shadows the exception.
+    let routes = RouteMatcher::public_by_default()
+        .protect("/api/**")
+        .public("/api/get_auth_provider*");
+
+    assert_eq!(get(&routes, "/api/get_auth_provider"), Access::Protected);
+}
+
+#[test]
+fn method_rules_only_match_their_methods() {
+    let routes = RouteMatcher::default()
+        .protect_for(&[Method::POST], "/report/**")
+        .public("/report/**");
+
+    assert_eq!(get(&routes, "/report/2023"), Access::Public);
+    assert_eq!(
+        routes.access(&Method::POST, "/report/2023"),
+        Access::Protected
+    );
+
+    let routes = RouteMatcher::default().public_for(&[Method::GET, Method::HEAD], "/live/*");
+
+    assert_eq!(get(&routes, "/live/1"), Access::Public);
+    assert_eq!(routes.access(&Method::HEAD, "/live/1"), Access::Public);
+    assert_eq!(routes.access(&Method::POST, "/live/1"), Access::Protected);
+}
@@ -0,0 +1,221 @@
+//! Expected values are worked out by hand from the 2023 federal brackets in
+//! `data/tax/us_federal_2023.json`.
+
+use leptos_start::list::{Budget, BudgetMode, Category, Entry, IncomeBasis, List};
+use leptos_start::tax::{FilingStatus, TaxProfile, TaxTable};
+use rust_decimal::Decimal;
+
+fn dollars(value: i64) -> Decimal {
+    Decimal::new(value, 0)
+}
+
+fn cents(value: i64) -> Decimal {
+    Decimal::new(value, 2)
+}
+
+fn profile(agi: i64) -> TaxProfile {
+    TaxProfile {
+        filing_status: FilingStatus::Single,
+        agi: dollars(agi),
+        other_itemized_deductions: Decimal::ZERO,
+        private_foundation_giving: Decimal::ZERO,
+    }
+}
+
+#[test]
+fn taxes_each_bracket_at_its_own_rate() {
+    let table = TaxTable::bundled();
+    let single = |income| table.tax(FilingStatus::Single, income);
+
+    assert_eq!(single(Decimal::ZERO), Decimal::ZERO);
+    assert_eq!(single(dollars(-5_000)), Decimal::ZERO);
+    // 10% of 11,000.
+    assert_eq!(single(dollars(11_000)), dollars(1_100));
+    assert_eq!(single(dollars(11_001)), cents(110_012));
+    // + 12% of 33,725.
+    assert_eq!(single(dollars(44_725)), dollars(5_147));
+    // + 22% of 50,650 + 24% of 4,625.
+    assert_eq!(single(dollars(100_000)), dollars(17_400));
+    // + 24% of 86,725 + 32% of 49,150 + 35% of 346,875 + 37% of 21,875.
+    assert_eq!(single(dollars(600_000)), dollars(182_332));
+
+    // 10% of 22,000 + 12% of 67,450 + 22% of 10,550.
+    assert_eq!(
+        table.tax(FilingStatus::MarriedFilingJointly, dollars(100_000)),
+        dollars(12_615)
+    );
+    // 1,100 + 12% of 1,345.678, rounded to cents.
+    assert_eq!(single(Decimal::new(12_345_678, 3)), cents(126_148));
+}
+
+#[test]
+fn marginal_rate_changes_above_each_threshold() {
+    let table = TaxTable::bundled();
+    let single = |income| table.marginal_rate(FilingStatus::Single, income);
+
+    assert_eq!(single(Decimal::ZERO), Decimal::ZERO);
+    assert_eq!(single(dollars(1)), cents(10));
+    assert_eq!(single(dollars(11_000)), cents(10));
+    assert_eq!(single(cents(1_100_001)), cents(12));
+    assert_eq!(single(dollars(95_375)), cents(22));
+    assert_eq!(single(dollars(95_376)), cents(24));
+    assert_eq!(single(dollars(1_000_000)), cents(37));
+
+    assert_eq!(
+        table.marginal_rate(FilingStatus::MarriedFilingSeparately, dollars(400_000)),
+        cents(37)
+    );
+    assert_eq!(
+        table.marginal_rate(FilingStatus::HeadOfHousehold, dollars(400_000)),
+        cents(35)
+    );
+}
+
+#[test]
+fn caps_giving_at_the_agi_limits() {
+    let table = TaxTable::bundled();
+    let capped = |private_foundation_giving, giving| {
+        table.capped_giving(
+            &TaxProfile {
+                private_foundation_giving: dollars(private_foundation_giving),
+                ..profile(100_000)
+            },
+            dollars(giving),
+        )
+    };
+
+    assert_eq!(capped(0, 10_000), (dollars(10_000), Decimal::ZERO));
+    assert_eq!(capped(0, 60_000), (dollars(60_000), Decimal::ZERO));
+    // 60% of AGI for public charities.
+    assert_eq!(capped(0, 70_000), (dollars(60_000), dollars(10_000)));
+    // 30% of AGI for private foundations.
+    assert_eq!(capped(40_000, 50_000), (dollars(40_000), dollars(10_000)));
+    // Private foundations only get what is left under the 60% cap.
+    assert_eq!(capped(20_000, 65_000), (dollars(60_000), dollars(5_000)));
+    // Private foundation giving can't be more than the giving.
+    assert_eq!(capped(5_000, 1_000), (dollars(1_000), Decimal::ZERO));
+}
+
+#[test]
+fn itemizes_when_giving_beats_the_standard_deduction() {
+    let table = TaxTable::bundled();
+    let profile = TaxProfile {
+        other_itemized_deductions: dollars(5_000),
+        ..profile(100_000)
+    };
+
+    let estimate = table.estimate(&profile, dollars(10_000));
+
+    assert_eq!(estimate.tax_year, 2023);
+    assert_eq!(estimate.deductible_giving, dollars(10_000));
+    assert_eq!(estimate.carryover, Decimal::ZERO);
+    assert_eq!(estimate.standard.deduction, dollars(13_850));
+    assert_eq!(estimate.standard.taxable_income, dollars(86_150));
+    // 5,147 + 22% of 41,425.
+    assert_eq!(estimate.standard.tax, cents(1_426_050));
+    assert_eq!(estimate.itemized.deduction, dollars(15_000));
+    assert_eq!(estimate.itemized.taxable_income, dollars(85_000));
+    // 5,147 + 22% of 40,275.
+    assert_eq!(estimate.itemized.tax, cents(1_400_750));
+    assert!(estimate.itemize);
+    assert_eq!(estimate.marginal_rate, cents(22));
+    // Only the 1,150 over the standard deduction saves tax.
+    assert_eq!(estimate.tax_savings, dollars(253));
+    assert_eq!(estimate.after_tax_cost, dollars(9_747));
+
+    // Two years of 14,007.50, against 11,807.50 on 75,000 then 14,260.50 on the standard
+    // deduction.
+    assert_eq!(estimate.bunching.annual_tax, dollars(28_015));
+    assert_eq!(estimate.bunching.bunched_tax, dollars(26_068));
+    assert_eq!(estimate.bunching.savings, dollars(1_947));
+}
+
+#[test]
+fn keeps_the_standard_deduction_for_small_gifts() {
+    let table = TaxTable::bundled();
+
+    let estimate = table.estimate(&profile(100_000), dollars(5_000));
+
+    assert!(!estimate.itemize);
+    assert_eq!(estimate.itemized.deduction, dollars(5_000));
+    assert_eq!(estimate.standard.tax, cents(1_426_050));
+    assert_eq!(estimate.tax_savings, Decimal::ZERO);
+    assert_eq!(estimate.after_tax_cost, dollars(5_000));
+    // Bunched, 10,000 is still under the standard deduction.
+    assert_eq!(estimate.bunching.savings, Decimal::ZERO);
+}
+
+#[test]
+fn carries_over_giving_above_the_cap() {
+    let table = TaxTable::bundled();
+
+    let estimate = table.estimate(&profile(50_000), dollars(40_000));
+
+    assert_eq!(estimate.deductible_giving, dollars(30_000));
+    assert_eq!(estimate.carryover, dollars(10_000));
+    // 1,100 + 12% of 9,000, against 1,100 + 12% of 25,150 without giving.
+    assert_eq!(estimate.itemized.tax, dollars(2_180));
+    assert_eq!(estimate.standard.tax, dollars(4_118));
+    assert_eq!(estimate.marginal_rate, cents(12));
+    assert_eq!(estimate.tax_savings, dollars(1_938));
+    assert_eq!(estimate.after_tax_cost, dollars(38_062));
+
+    // Bunching only adds to the carryover, the second year gets the standard deduction.
+    assert_eq!(estimate.bunching.annual_tax, dollars(4_360));
+    assert_eq!(estimate.bunching.bunched_tax, dollars(6_298));
+    assert_eq!(estimate.bunching.savings, dollars(-1_938));
+}
+
+#[test]
+fn estimates_the_planned_giving_of_a_list() {
+    let entry = |id, ein| Entry {
+        id,
+        category_id: 1,
+        ein,
+        multiplier: Decimal::ONE,
+        percent_override: None,
+        value_override: None,
+        enabled: true,
+        version: 1,
+    };
+    let list = List {
+        id: 1,
+        user_id: "user_test".to_owned(),
+        scenario_of: None,
+        name: None,
+        version: 1,
+        budget: Budget {
+            id: 1,
+            list_id: 1,
+            total_value: dollars(100_000),
+            donation_percent: cents(10),
+            value_override: None,
+            mode: BudgetMode::Percent,
+            income_basis: IncomeBasis::Gross,
+            net_value: None,
+            brackets: Vec::new(),
+            version: 1,
+        },
+        categories: vec![Category {
+            id: 1,
+            list_id: 1,
+            name: "Health".to_owned(),
+            entries: vec![entry(1, 123_456_789), entry(2, 987_654_321)],
+            multiplier: Decimal::ONE,
+            percent_override: None,
+            value_override: None,
+            enabled: true,
+            version: 1,
+        }],
+    };
+    let profile = TaxProfile {
+        other_itemized_deductions: dollars(5_000),
+        ..profile(100_000)
+    };
+
+    assert_eq!(list.planned_giving(), dollars(10_000));
+    assert_eq!(
+        list.estimate_taxes(&TaxTable::bundled(), &profile),
+        TaxTable::bundled().estimate(&profile, dollars(10_000))
+    );
+}
@@ -0,0 +1,227 @@
+use leptos_start::database::nonprofits::{load_publication_78, LoadSummary};
+use leptos_start::entity::{donation, nonprofit};
+use leptos_start::list::List;
+use leptos_start::report::{Deductibility, TaxYearReport};
+use rust_decimal::Decimal;
+use sea_orm::{prelude::Date, ActiveModelTrait, DatabaseConnection, EntityTrait, NotSet, Set};
+
+mod common;
+
+use common::db::database;
+
+/// Laid out like the IRS download, blank lines first.
+const PUBLICATION_78: &str = "
+
+111111111|HEALTH FOR ALL|NEW YORK|NY|United States|PC
+222222222|ANIMAL SHELTER INC|AUSTIN|TX|United States|PC,POF
+333333333|FAMILY FOUNDATION|DENVER|CO|United States|PF
+not an ein|BROKEN ROW|DENVER|CO|United States|PC
+444444444||DENVER|CO|United States|PC
+";
+
+fn dollars(value: i64) -> Decimal {
+    Decimal::new(value, 0)
+}
+
+fn date(year: i32, month: u32, day: u32) -> Date {
+    Date::from_ymd_opt(year, month, day).unwrap()
+}
+
+async fn donate(
+    db: &DatabaseConnection,
+    list: &List,
+    (ein, amount, donated_on, receipt_reference): (i32, Decimal, Date, Option<&str>),
+) {
+    donation::ActiveModel {
+        id: NotSet,
+        list_id: Set(list.id),
+        ein: Set(ein),
+        amount: Set(amount),
+        donated_on: Set(donated_on),
+        receipt_reference: Set(receipt_reference.map(str::to_owned)),
+    }
+    .insert(db)
+    .await
+    .unwrap();
+}
+
+/// A list with an income of 80,000 and donations in 2022, 2023 and 2024.
+async fn list_with_donations(db: &DatabaseConnection) -> List {
+    let mut list = List::init_list(db, "user_a".to_owned()).await.unwrap();
+    list.budget.total_value = dollars(80_000);
+    let list = list.save(db, "user_a").await.unwrap();
+
+    for donation in [
+        (111_111_111, dollars(500), date(2023, 1, 1), Some("R-1")),
+        (111_111_111, Decimal::new(25050, 2), date(2023, 6, 15), None),
+        (222_222_222, dollars(1_000), date(2023, 12, 31), Some("R-2")),
+        (333_333_333, dollars(2_000), date(2023, 3, 1), Some("R-3")),
+        (555_555_555, dollars(300), date(2023, 4, 1), None),
+        (666_666_666, dollars(100), date(2023, 5, 1), None),
+        (111_111_111, dollars(9_999), date(2022, 12, 31), None),
+        (111_111_111, dollars(9_999), date(2024, 1, 1), None),
+    ] {
+        donate(db, &list, donation).await;
+    }
+
+    let other = List::init_list(db, "user_b".to_owned()).await.unwrap();
+    donate(
+        db,
+        &other,
+        (111_111_111, dollars(9_999), date(2023, 7, 1), None),
+    )
+    .await;
+
+    list
+}
+
+#[actix_web::test]
+async fn loads_publication_78_and_skips_broken_lines() {
+    let db = database().await;
+
+    let summary = load_publication_78(&db, PUBLICATION_78.as_bytes())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        summary,
+        LoadSummary {
+            loaded: 3,
+            skipped: vec![6, 7],
+        }
+    );
+    let shelter = nonprofit::Entity::find_by_id(222_222_222)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(shelter.name, "ANIMAL SHELTER INC");
+    assert_eq!(shelter.deductibility_code.as_deref(), Some("PC,POF"));
+
+    // Loading a newer file updates the organizations it lists.
+    load_publication_78(
+        &db,
+        "222222222|ANIMAL RESCUE INC|AUSTIN|TX|United States|PC\n".as_bytes(),
+    )
+    .await
+    .unwrap();
+    let shelter = nonprofit::Entity::find_by_id(222_222_222)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(shelter.name, "ANIMAL RESCUE INC");
+    assert_eq!(shelter.deductibility_code.as_deref(), Some("PC"));
+    assert_eq!(nonprofit::Entity::find().all(&db).await.unwrap().len(), 3);
+}
+
+#[actix_web::test]
+async fn totals_a_calendar_year_per_charity() {
+    let db = database().await;
+    load_publication_78(&db, PUBLICATION_78.as_bytes())
+        .await
+        .unwrap();
+    // Known, but not eligible for deductible gifts.
+    nonprofit::ActiveModel {
+        ein: Set(555_555_555),
+        name: Set("SOCIAL CLUB".to_owned()),
+        deductibility_code: Set(None),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    let list = list_with_donations(&db).await;
+
+    let report = TaxYearReport::build(&db, &list, 2023).await.unwrap();
+
+    let charities: Vec<_> = report
+        .charities
+        .iter()
+        .map(|charity| {
+            (
+                charity.ein,
+                charity.name.as_deref(),
+                charity.deductibility.clone(),
+                charity.total,
+                charity.donation_count,
+                charity.receipts.clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        charities,
+        [
+            (
+                111_111_111,
+                Some("HEALTH FOR ALL"),
+                Deductibility::Deductible("PC".to_owned()),
+                Decimal::new(75050, 2),
+                2,
+                vec!["R-1".to_owned()],
+            ),
+            (
+                222_222_222,
+                Some("ANIMAL SHELTER INC"),
+                Deductibility::Deductible("PC,POF".to_owned()),
+                dollars(1_000),
+                1,
+                vec!["R-2".to_owned()],
+            ),
+            (
+                333_333_333,
+                Some("FAMILY FOUNDATION"),
+                Deductibility::Deductible("PF".to_owned()),
+                dollars(2_000),
+                1,
+                vec!["R-3".to_owned()],
+            ),
+            (
+                555_555_555,
+                Some("SOCIAL CLUB"),
+                Deductibility::NotDeductible,
+                dollars(300),
+                1,
+                vec![],
+            ),
+            (
+                666_666_666,
+                None,
+                Deductibility::Unknown,
+                dollars(100),
+                1,
+                vec![]
+            ),
+        ]
+    );
+
+    assert_eq!(report.year, 2023);
+    assert_eq!(report.income, dollars(80_000));
+    // 750.50 + 1,000 + 2,000 + 300 + 100.
+    assert_eq!(report.total, Decimal::new(415050, 2));
+    // Without the social club and the unknown EIN.
+    assert_eq!(report.deductible_total, Decimal::new(375050, 2));
+    // 4,150.50 / 80,000 = 0.05188125.
+    assert_eq!(report.share_of_income, Decimal::new(519, 4));
+
+    let html = report.to_html();
+    assert!(html.contains("<p>Total donated: $4150.50</p>"));
+    assert!(html.contains("<p>Deductible donations: $3750.50</p>"));
+    assert!(html.contains("<p>Share of income: 5.19%</p>"));
+    assert!(html.contains("<td>11-1111111</td><td>HEALTH FOR ALL</td><td>Deductible (PC)</td>"));
+    assert!(report.to_pdf().unwrap().starts_with(b"%PDF"));
+}
+
+#[actix_web::test]
+async fn reports_an_empty_year_without_income() {
+    let db = database().await;
+    let mut list = List::init_list(&db, "user_a".to_owned()).await.unwrap();
+    list.budget.total_value = Decimal::ZERO;
+    let list = list.save(&db, "user_a").await.unwrap();
+
+    let report = TaxYearReport::build(&db, &list, 2023).await.unwrap();
+
+    assert!(report.charities.is_empty());
+    assert_eq!(report.total, Decimal::ZERO);
+    assert_eq!(report.deductible_total, Decimal::ZERO);
+    assert_eq!(report.share_of_income, Decimal::ZERO);
+}